@@ -62,12 +62,17 @@ pub type DefaultTimer = StdTimer;
 /// could be called many times, so it should be fast to add low overhead
 pub trait StopCondition<Obj: Objective>: Clone {
     fn stop(&mut self, primal_bound: Obj, dual_bound: Obj) -> bool;
+    /// fraction of the budget used as of the last call to stop, in 0..=1, if it is known
+    fn progress(&self) -> Option<f64> {
+        None
+    }
 }
 
 pub struct TimeStop<T: Timer> {
     timer: T,
     start: T::Instant,
     duration: std::time::Duration,
+    progress: f64,
 }
 impl<T: Timer> Clone for TimeStop<T> {
     fn clone(&self) -> Self {
@@ -76,6 +81,7 @@ impl<T: Timer> Clone for TimeStop<T> {
             timer: self.timer.clone(),
             start: time,
             duration: self.duration,
+            progress: 0.0,
         }
     }
 }
@@ -86,12 +92,18 @@ impl<T: Timer> TimeStop<T> {
             timer,
             start: time,
             duration,
+            progress: 0.0,
         }
     }
 }
 impl<T: Timer, Obj: Objective> StopCondition<Obj> for TimeStop<T> {
     fn stop(&mut self, _primal_bound: Obj, _dual_bound: Obj) -> bool {
-        self.timer.time() >= self.start + self.duration
+        let elapsed = self.timer.time() - self.start;
+        self.progress = (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0);
+        elapsed >= self.duration
+    }
+    fn progress(&self) -> Option<f64> {
+        Some(self.progress)
     }
 }
 
@@ -0,0 +1,933 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Write;
+
+use crate::lp::model::*;
+
+/// error while reading a model file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line of the error
+    pub line: usize,
+    pub msg: String,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+impl std::error::Error for ParseError {}
+fn err<T>(line: usize, msg: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError {
+        line,
+        msg: msg.into(),
+    })
+}
+
+/// names that would be read back as keywords or numbers
+const RESERVED: [&str; 20] = [
+    "min", "minimize", "minimum", "max", "maximize", "maximum", "st", "s.t.", "st.", "subject",
+    "such", "bound", "bounds", "general", "generals", "gen", "binary", "binaries", "bin", "end",
+];
+
+/// variable names usable in both formats: unique, only made of ascii alphanumerics, '_' and '.',
+/// not starting with a digit or '.', unnamed variables are called x{id}
+fn export_names(mb: &ModelBuilder) -> Vec<String> {
+    let mut used = HashSet::new();
+    let mut names = Vec::with_capacity(mb.variables().len());
+    for (i, v) in mb.variables().iter().enumerate() {
+        let mut name: String = v
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.is_empty() {
+            name = format!("x{}", i);
+        }
+        if name.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            name.insert(0, '_');
+        }
+        let lower = name.to_lowercase();
+        if RESERVED.contains(&lower.as_str())
+            || ["free", "inf", "infinity", "to", "that"].contains(&lower.as_str())
+            || lower.starts_with('e') && lower[1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            name.push('_');
+        }
+        while !used.insert(name.clone()) {
+            name = format!("{}_{}", name, i);
+        }
+        names.push(name);
+    }
+    names
+}
+
+fn fmt_bound(v: f64) -> String {
+    if v == f64::INFINITY {
+        "+inf".to_string()
+    } else if v == f64::NEG_INFINITY {
+        "-inf".to_string()
+    } else {
+        format!("{}", v)
+    }
+}
+
+/// writes ` + c name` terms, wrapping long expressions over multiple lines
+fn write_lp_terms<W: Write>(w: &mut W, names: &[String], expr: &[(usize, f64)]) -> fmt::Result {
+    for (k, &(id, mul)) in expr.iter().enumerate() {
+        if k > 0 && k % 8 == 0 {
+            write!(w, "\n  ")?;
+        }
+        write!(w, " {}", if mul < 0.0 { '-' } else { '+' })?;
+        if mul.abs() != 1.0 {
+            write!(w, " {}", mul.abs())?;
+        }
+        write!(w, " {}", names[id])?;
+    }
+    if expr.is_empty() {
+        write!(w, " 0")?;
+    }
+    Ok(())
+}
+
+/// writes the model in the CPLEX LP format,
+/// every variable gets a line in the bounds section so none is lost, removed ones are skipped.
+/// when read back variables are numbered in order of first appearance,
+/// use ModelBuilder::var_by_name to find them
+pub fn write_lp<W: Write>(mb: &ModelBuilder, w: &mut W) -> fmt::Result {
+    let names = export_names(mb);
+    writeln!(w, "\\ written by mopper")?;
+    match mb.sense {
+        Sense::Minimize => writeln!(w, "Minimize")?,
+        Sense::Maximize => writeln!(w, "Maximize")?,
+    }
+    write!(w, " obj:")?;
+    write_lp_terms(w, &names, &mb.objective.variables)?;
+    if mb.objective.constant != 0.0 {
+        let c = mb.objective.constant;
+        write!(w, " {} {}", if c < 0.0 { '-' } else { '+' }, c.abs())?;
+    }
+    writeln!(w)?;
+    writeln!(w, "Subject To")?;
+    for (i, c) in mb.constraints().iter().enumerate() {
+        let (lo, hi) = c.row_bounds();
+        write!(w, " c{}:", i)?;
+        if lo > f64::NEG_INFINITY && hi < f64::INFINITY && lo != hi {
+            write!(w, " {} <=", fmt_bound(lo))?;
+        }
+        write_lp_terms(w, &names, &c.expr.variables)?;
+        if lo == hi {
+            writeln!(w, " = {}", fmt_bound(lo))?;
+        } else if hi < f64::INFINITY {
+            writeln!(w, " <= {}", fmt_bound(hi))?;
+        } else {
+            writeln!(w, " >= {}", fmt_bound(lo))?;
+        }
+    }
+    writeln!(w, "Bounds")?;
+    for (v, name) in mb
+        .variables()
+        .iter()
+        .zip(names.iter())
+        .filter(|(v, _)| !v.removed)
+    {
+        if v.lb == v.ub {
+            writeln!(w, " {} = {}", name, fmt_bound(v.lb))?;
+        } else if v.lb == f64::NEG_INFINITY && v.ub == f64::INFINITY {
+            writeln!(w, " {} free", name)?;
+        } else if v.ub == f64::INFINITY {
+            writeln!(w, " {} >= {}", name, fmt_bound(v.lb))?;
+        } else {
+            writeln!(w, " {} <= {} <= {}", fmt_bound(v.lb), name, fmt_bound(v.ub))?;
+        }
+    }
+    if mb.variables().iter().any(|v| v.integer) {
+        writeln!(w, "General")?;
+        for (v, name) in mb.variables().iter().zip(names.iter()) {
+            if v.integer {
+                writeln!(w, " {}", name)?;
+            }
+        }
+    }
+    writeln!(w, "End")
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Num(f64),
+    Id(String),
+    Le,
+    Ge,
+    Eq,
+    Colon,
+    Plus,
+    Minus,
+}
+
+fn tokenize_lp(s: &str) -> Result<Vec<(Tok, usize)>, ParseError> {
+    let mut toks = vec![];
+    for (l, line) in s.lines().enumerate() {
+        let line_no = l + 1;
+        let line = line.split('\\').next().unwrap_or("");
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            let tok = match c {
+                '<' | '>' | '=' => {
+                    i += 1;
+                    let next = chars.get(i).copied();
+                    let op = match (c, next) {
+                        ('<', Some('=')) | ('=', Some('<')) => Some(Tok::Le),
+                        ('>', Some('=')) | ('=', Some('>')) => Some(Tok::Ge),
+                        _ => None,
+                    };
+                    if op.is_some() {
+                        i += 1;
+                    }
+                    op.unwrap_or(match c {
+                        '<' => Tok::Le,
+                        '>' => Tok::Ge,
+                        _ => Tok::Eq,
+                    })
+                }
+                ':' => {
+                    i += 1;
+                    Tok::Colon
+                }
+                '+' => {
+                    i += 1;
+                    Tok::Plus
+                }
+                '-' => {
+                    i += 1;
+                    Tok::Minus
+                }
+                _ if c.is_ascii_digit() || c == '.' => {
+                    while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                        i += 1;
+                    }
+                    if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                        let mut j = i + 1;
+                        if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                            j += 1;
+                        }
+                        if j < chars.len() && chars[j].is_ascii_digit() {
+                            i = j;
+                            while i < chars.len() && chars[i].is_ascii_digit() {
+                                i += 1;
+                            }
+                        }
+                    }
+                    let text: String = chars[start..i].iter().collect();
+                    match text.parse::<f64>() {
+                        Ok(v) => Tok::Num(v),
+                        Err(_) => return err(line_no, format!("invalid number {}", text)),
+                    }
+                }
+                _ => {
+                    while i < chars.len()
+                        && !chars[i].is_whitespace()
+                        && !"<>=:+-".contains(chars[i])
+                    {
+                        i += 1;
+                    }
+                    Tok::Id(chars[start..i].iter().collect())
+                }
+            };
+            toks.push((tok, line_no));
+        }
+    }
+    Ok(toks)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LpSection {
+    Objective(Sense),
+    Constraints,
+    Bounds,
+    General,
+    Binary,
+    End,
+}
+
+struct LpParser {
+    toks: Vec<(Tok, usize)>,
+    pos: usize,
+    mb: ModelBuilder,
+    ids: HashMap<String, usize>,
+}
+impl LpParser {
+    fn peek(&self, k: usize) -> Option<&Tok> {
+        self.toks.get(self.pos + k).map(|t| &t.0)
+    }
+    fn line(&self) -> usize {
+        self.toks
+            .get(self.pos)
+            .or(self.toks.last())
+            .map_or(0, |t| t.1)
+    }
+    /// section starting at the current token and its number of tokens
+    fn section(&self) -> Result<Option<(LpSection, usize)>, ParseError> {
+        let Some(Tok::Id(s)) = self.peek(0) else {
+            return Ok(None);
+        };
+        let next = match self.peek(1) {
+            Some(Tok::Id(n)) => n.to_lowercase(),
+            _ => String::new(),
+        };
+        Ok(Some(match s.to_lowercase().as_str() {
+            "min" | "minimize" | "minimum" => (LpSection::Objective(Sense::Minimize), 1),
+            "max" | "maximize" | "maximum" => (LpSection::Objective(Sense::Maximize), 1),
+            "subject" if next == "to" => (LpSection::Constraints, 2),
+            "such" if next == "that" => (LpSection::Constraints, 2),
+            "st" | "s.t." | "st." => (LpSection::Constraints, 1),
+            "bound" | "bounds" => (LpSection::Bounds, 1),
+            "general" | "generals" | "gen" => (LpSection::General, 1),
+            "binary" | "binaries" | "bin" => (LpSection::Binary, 1),
+            "end" => (LpSection::End, 1),
+            "semi-continuous" | "semis" | "semi" | "sos" => {
+                return err(self.line(), format!("unsupported section {}", s))
+            }
+            _ => return Ok(None),
+        }))
+    }
+    fn var(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.mb.variables().len();
+        self.mb.add_var(0.0, f64::INFINITY, false, name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+    fn is_label(&self) -> bool {
+        matches!(self.peek(0), Some(Tok::Id(_))) && self.peek(1) == Some(&Tok::Colon)
+    }
+    /// a term can start here and it is not a section keyword or a constraint label
+    fn is_var(&self, k: usize) -> Result<bool, ParseError> {
+        Ok(match self.peek(k) {
+            Some(Tok::Id(s)) => {
+                let lower = s.to_lowercase();
+                self.peek(k + 1) != Some(&Tok::Colon)
+                    && lower != "inf"
+                    && lower != "infinity"
+                    && (k > 0 || self.section()?.is_none())
+            }
+            _ => false,
+        })
+    }
+    fn parse_expr(&mut self) -> Result<Expression, ParseError> {
+        let mut constant = 0.0;
+        let mut terms = vec![];
+        loop {
+            let mut sign = 1.0;
+            let mut has_sign = false;
+            while let Some(t @ (Tok::Plus | Tok::Minus)) = self.peek(0) {
+                if *t == Tok::Minus {
+                    sign = -sign;
+                }
+                has_sign = true;
+                self.pos += 1;
+            }
+            match self.peek(0).cloned() {
+                Some(Tok::Num(v)) => {
+                    self.pos += 1;
+                    if self.is_var(0)? {
+                        let Some(Tok::Id(name)) = self.peek(0).cloned() else {
+                            unreachable!()
+                        };
+                        self.pos += 1;
+                        terms.push((self.var(&name), sign * v));
+                    } else {
+                        constant += sign * v;
+                    }
+                }
+                Some(Tok::Id(name)) if self.is_var(0)? => {
+                    self.pos += 1;
+                    terms.push((self.var(&name), sign));
+                }
+                _ if has_sign => return err(self.line(), "expected a term after sign"),
+                _ => break,
+            }
+        }
+        Ok(Expression::from_terms(constant, terms))
+    }
+    /// a signed number or infinity
+    fn parse_value(&mut self) -> Result<f64, ParseError> {
+        let mut sign = 1.0;
+        while let Some(t @ (Tok::Plus | Tok::Minus)) = self.peek(0) {
+            if *t == Tok::Minus {
+                sign = -sign;
+            }
+            self.pos += 1;
+        }
+        let v = match self.peek(0) {
+            Some(Tok::Num(v)) => *v,
+            Some(Tok::Id(s)) if ["inf", "infinity"].contains(&s.to_lowercase().as_str()) => {
+                f64::INFINITY
+            }
+            _ => return err(self.line(), "expected a number"),
+        };
+        self.pos += 1;
+        Ok(sign * v)
+    }
+    /// a value followed by a comparison starts at the current token
+    fn starts_with_value(&self) -> bool {
+        let mut k = 0;
+        while matches!(self.peek(k), Some(Tok::Plus | Tok::Minus)) {
+            k += 1;
+        }
+        let value = match self.peek(k) {
+            Some(Tok::Num(_)) => true,
+            Some(Tok::Id(s)) => ["inf", "infinity"].contains(&s.to_lowercase().as_str()),
+            _ => false,
+        };
+        value && matches!(self.peek(k + 1), Some(Tok::Le | Tok::Ge | Tok::Eq))
+    }
+    fn parse_op(&mut self) -> Result<Tok, ParseError> {
+        match self.peek(0) {
+            Some(t @ (Tok::Le | Tok::Ge | Tok::Eq)) => {
+                let t = t.clone();
+                self.pos += 1;
+                Ok(t)
+            }
+            _ => err(self.line(), "expected <=, >= or ="),
+        }
+    }
+    fn parse_constraint(&mut self) -> Result<(), ParseError> {
+        if self.is_label() {
+            self.pos += 2;
+        }
+        let (expr, lo, hi) = if self.starts_with_value() {
+            let first = self.parse_value()?;
+            let op = self.parse_op()?;
+            let expr = self.parse_expr()?;
+            if self.parse_op()? != op || op == Tok::Eq {
+                return err(self.line(), "ranged constraint needs two <= or two >=");
+            }
+            let second = self.parse_value()?;
+            if op == Tok::Le {
+                (expr, first, second)
+            } else {
+                (expr, second, first)
+            }
+        } else {
+            let expr = self.parse_expr()?;
+            let op = self.parse_op()?;
+            let v = self.parse_value()?;
+            match op {
+                Tok::Le => (expr, f64::NEG_INFINITY, v),
+                Tok::Ge => (expr, v, f64::INFINITY),
+                _ => (expr, v, v),
+            }
+        };
+        self.mb.add_constraint(Constraint { expr, lo, hi });
+        Ok(())
+    }
+    fn parse_bound(&mut self) -> Result<(), ParseError> {
+        if let (Some(Tok::Id(name)), Some(Tok::Id(free))) = (self.peek(0), self.peek(1)) {
+            if free.to_lowercase() == "free" {
+                let id = self.var(&name.clone());
+                self.pos += 2;
+                self.mb
+                    .set_bounds(VariableId(id), f64::NEG_INFINITY, f64::INFINITY);
+                return Ok(());
+            }
+        }
+        let line = self.line();
+        let var = |p: &mut Self| match p.peek(0).cloned() {
+            Some(Tok::Id(name)) => {
+                p.pos += 1;
+                Ok(p.var(&name))
+            }
+            _ => err(line, "expected a variable"),
+        };
+        let (id, bounds) = if self.starts_with_value() {
+            let v = self.parse_value()?;
+            let op = self.parse_op()?;
+            let id = var(self)?;
+            let mut bounds = vec![(op.clone(), v, true)];
+            if matches!(self.peek(0), Some(Tok::Le | Tok::Ge | Tok::Eq)) {
+                let op2 = self.parse_op()?;
+                bounds.push((op2, self.parse_value()?, false));
+            }
+            (id, bounds)
+        } else {
+            let id = var(self)?;
+            let op = self.parse_op()?;
+            (id, vec![(op, self.parse_value()?, false)])
+        };
+        for (op, v, value_first) in bounds {
+            let info = &self.mb.variables()[id];
+            let (lb, ub) = match (op, value_first) {
+                (Tok::Eq, _) => (v, v),
+                (Tok::Le, true) | (Tok::Ge, false) => (v, info.ub),
+                _ => (info.lb, v),
+            };
+            self.mb.set_bounds(VariableId(id), lb, ub);
+        }
+        Ok(())
+    }
+}
+
+/// reads a model in the CPLEX LP format, constraint names are discarded
+pub fn read_lp(s: &str) -> Result<ModelBuilder, ParseError> {
+    let mut p = LpParser {
+        toks: tokenize_lp(s)?,
+        pos: 0,
+        mb: ModelBuilder::new(),
+        ids: HashMap::new(),
+    };
+    let mut section = match p.section()? {
+        Some((LpSection::Objective(sense), n)) => {
+            p.pos += n;
+            p.mb.sense = sense;
+            LpSection::Objective(sense)
+        }
+        _ => return err(p.line(), "expected Minimize or Maximize"),
+    };
+    while p.pos < p.toks.len() {
+        if let Some((s, n)) = p.section()? {
+            if matches!(s, LpSection::Objective(_)) {
+                return err(p.line(), "repeated objective section");
+            }
+            p.pos += n;
+            section = s;
+            continue;
+        }
+        match section {
+            LpSection::Objective(_) => {
+                if p.is_label() {
+                    p.pos += 2;
+                }
+                p.mb.objective = p.parse_expr()?;
+                if p.pos < p.toks.len() && p.section()?.is_none() {
+                    return err(p.line(), "unexpected token in objective");
+                }
+            }
+            LpSection::Constraints => p.parse_constraint()?,
+            LpSection::Bounds => p.parse_bound()?,
+            LpSection::General | LpSection::Binary => {
+                let Some(Tok::Id(name)) = p.peek(0).cloned() else {
+                    return err(p.line(), "expected a variable");
+                };
+                p.pos += 1;
+                let id = p.var(&name);
+                p.mb.set_integer(VariableId(id), true);
+                if section == LpSection::Binary {
+                    p.mb.set_bounds(VariableId(id), 0.0, 1.0);
+                }
+            }
+            LpSection::End => return err(p.line(), "unexpected token after End"),
+        }
+    }
+    Ok(p.mb)
+}
+
+/// writes the model in the free MPS format, the objective constant is written
+/// as the opposite of the right hand side of the objective row, removed variables are skipped
+pub fn write_mps<W: Write>(mb: &ModelBuilder, w: &mut W) -> fmt::Result {
+    let names = export_names(mb);
+    let n = mb.variables().len();
+    let rows: Vec<(f64, f64)> = mb.constraints().iter().map(|c| c.row_bounds()).collect();
+    writeln!(w, "NAME mopper")?;
+    if mb.sense == Sense::Maximize {
+        writeln!(w, "OBJSENSE")?;
+        writeln!(w, "    MAX")?;
+    }
+    writeln!(w, "ROWS")?;
+    writeln!(w, " N  obj")?;
+    for (i, &(lo, hi)) in rows.iter().enumerate() {
+        let t = if lo == hi {
+            'E'
+        } else if lo == f64::NEG_INFINITY && hi == f64::INFINITY {
+            'N'
+        } else if lo == f64::NEG_INFINITY {
+            'L'
+        } else {
+            'G'
+        };
+        writeln!(w, " {}  c{}", t, i)?;
+    }
+    let mut cols = vec![Vec::<(String, f64)>::new(); n];
+    for &(j, mul) in mb.objective.variables.iter() {
+        cols[j].push(("obj".to_string(), mul));
+    }
+    for (i, c) in mb.constraints().iter().enumerate() {
+        for &(j, mul) in c.expr.variables.iter() {
+            cols[j].push((format!("c{}", i), mul));
+        }
+    }
+    writeln!(w, "COLUMNS")?;
+    let mut in_marker = false;
+    for j in 0..n {
+        if mb.variables()[j].removed {
+            continue;
+        }
+        if mb.variables()[j].integer != in_marker {
+            in_marker = !in_marker;
+            let kind = if in_marker { "INTORG" } else { "INTEND" };
+            writeln!(w, "    MARKER  'MARKER'  '{}'", kind)?;
+        }
+        if cols[j].is_empty() {
+            writeln!(w, "    {}  obj  0", names[j])?;
+        }
+        for (row, mul) in cols[j].iter() {
+            writeln!(w, "    {}  {}  {}", names[j], row, mul)?;
+        }
+    }
+    if in_marker {
+        writeln!(w, "    MARKER  'MARKER'  'INTEND'")?;
+    }
+    writeln!(w, "RHS")?;
+    if mb.objective.constant != 0.0 {
+        writeln!(w, "    RHS  obj  {}", -mb.objective.constant)?;
+    }
+    for (i, &(lo, hi)) in rows.iter().enumerate() {
+        let rhs = if lo == f64::NEG_INFINITY { hi } else { lo };
+        if rhs != 0.0 && rhs.is_finite() {
+            writeln!(w, "    RHS  c{}  {}", i, rhs)?;
+        }
+    }
+    if rows
+        .iter()
+        .any(|&(lo, hi)| lo != hi && lo.is_finite() && hi.is_finite())
+    {
+        writeln!(w, "RANGES")?;
+        for (i, &(lo, hi)) in rows.iter().enumerate() {
+            if lo != hi && lo.is_finite() && hi.is_finite() {
+                writeln!(w, "    RNG  c{}  {}", i, hi - lo)?;
+            }
+        }
+    }
+    writeln!(w, "BOUNDS")?;
+    for (v, name) in mb
+        .variables()
+        .iter()
+        .zip(names.iter())
+        .filter(|(v, _)| !v.removed)
+    {
+        if v.lb == v.ub {
+            writeln!(w, " FX BND  {}  {}", name, v.lb)?;
+            continue;
+        }
+        if v.lb == f64::NEG_INFINITY && v.ub == f64::INFINITY {
+            writeln!(w, " FR BND  {}", name)?;
+            continue;
+        }
+        if v.lb == f64::NEG_INFINITY {
+            writeln!(w, " MI BND  {}", name)?;
+        } else if v.lb != 0.0 || v.ub < 0.0 {
+            writeln!(w, " LO BND  {}  {}", name, v.lb)?;
+        }
+        if v.ub < f64::INFINITY {
+            writeln!(w, " UP BND  {}  {}", name, v.ub)?;
+        } else if v.integer {
+            writeln!(w, " PL BND  {}", name)?;
+        }
+    }
+    writeln!(w, "ENDATA")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MpsSection {
+    None,
+    ObjSense,
+    Rows,
+    Columns,
+    Rhs,
+    Ranges,
+    Bounds,
+}
+
+/// reads a model in the free MPS format, row names are discarded
+/// and rows of type N other than the objective become free constraints
+pub fn read_mps(s: &str) -> Result<ModelBuilder, ParseError> {
+    let mut mb = ModelBuilder::new();
+    let mut section = MpsSection::None;
+    let mut obj_row: Option<String> = None;
+    // row name -> index in rows, rows are (type, terms, rhs, range)
+    let mut row_ids = HashMap::<String, usize>::new();
+    let mut rows = Vec::<(char, Vec<(usize, f64)>, f64, Option<f64>)>::new();
+    let mut obj_terms = vec![];
+    let mut col_ids = HashMap::<String, usize>::new();
+    let mut integer = false;
+    for (l, line) in s.lines().enumerate() {
+        let line_no = l + 1;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        let num = |f: &str| {
+            f.parse::<f64>()
+                .or_else(|_| err(line_no, format!("invalid number {}", f)))
+        };
+        if !line.starts_with(char::is_whitespace) {
+            section = match fields[0].to_uppercase().as_str() {
+                "NAME" => MpsSection::None,
+                "OBJSENSE" if fields.len() > 1 => {
+                    mb.sense = parse_sense(fields[1], line_no)?;
+                    MpsSection::None
+                }
+                "OBJSENSE" => MpsSection::ObjSense,
+                "ROWS" => MpsSection::Rows,
+                "COLUMNS" => MpsSection::Columns,
+                "RHS" => MpsSection::Rhs,
+                "RANGES" => MpsSection::Ranges,
+                "BOUNDS" => MpsSection::Bounds,
+                "ENDATA" => break,
+                _ => return err(line_no, format!("unknown section {}", fields[0])),
+            };
+            continue;
+        }
+        match section {
+            MpsSection::None => return err(line_no, "data outside of a section"),
+            MpsSection::ObjSense => mb.sense = parse_sense(fields[0], line_no)?,
+            MpsSection::Rows => {
+                if fields.len() != 2 {
+                    return err(line_no, "expected row type and name");
+                }
+                let t = fields[0].to_uppercase();
+                if t == "N" && obj_row.is_none() {
+                    obj_row = Some(fields[1].to_string());
+                    continue;
+                }
+                let t = match t.as_str() {
+                    "N" => 'N',
+                    "L" => 'L',
+                    "G" => 'G',
+                    "E" => 'E',
+                    _ => return err(line_no, format!("unknown row type {}", fields[0])),
+                };
+                row_ids.insert(fields[1].to_string(), rows.len());
+                rows.push((t, vec![], 0.0, None));
+            }
+            MpsSection::Columns => {
+                if fields.len() >= 3 && fields[1] == "'MARKER'" {
+                    match fields[2] {
+                        "'INTORG'" => integer = true,
+                        "'INTEND'" => integer = false,
+                        _ => return err(line_no, format!("unknown marker {}", fields[2])),
+                    }
+                    continue;
+                }
+                if fields.len().is_multiple_of(2) {
+                    return err(line_no, "expected column name and (row, value) pairs");
+                }
+                let j = *col_ids.entry(fields[0].to_string()).or_insert_with(|| {
+                    mb.add_var(0.0, f64::INFINITY, integer, fields[0].to_string())
+                        .0
+                });
+                for pair in fields[1..].chunks(2) {
+                    let v = num(pair[1])?;
+                    if obj_row.as_deref() == Some(pair[0]) {
+                        obj_terms.push((j, v));
+                    } else if let Some(&r) = row_ids.get(pair[0]) {
+                        rows[r].1.push((j, v));
+                    } else {
+                        return err(line_no, format!("unknown row {}", pair[0]));
+                    }
+                }
+            }
+            MpsSection::Rhs | MpsSection::Ranges => {
+                // the set name is optional
+                let pairs = if !fields.len().is_multiple_of(2) {
+                    &fields[1..]
+                } else {
+                    &fields[..]
+                };
+                for pair in pairs.chunks(2) {
+                    let v = num(pair[1])?;
+                    if obj_row.as_deref() == Some(pair[0]) {
+                        if section == MpsSection::Rhs {
+                            mb.objective.constant = -v;
+                        }
+                    } else if let Some(&r) = row_ids.get(pair[0]) {
+                        if section == MpsSection::Rhs {
+                            rows[r].2 = v;
+                        } else {
+                            rows[r].3 = Some(v);
+                        }
+                    } else {
+                        return err(line_no, format!("unknown row {}", pair[0]));
+                    }
+                }
+            }
+            MpsSection::Bounds => {
+                let t = fields[0].to_uppercase();
+                let needs_value = ["UP", "LO", "FX", "LI", "UI"].contains(&t.as_str());
+                let (col, value) = match fields.len() {
+                    4 => (fields[2], Some(num(fields[3])?)),
+                    3 if needs_value => (fields[1], Some(num(fields[2])?)),
+                    3 if col_ids.contains_key(fields[1]) && fields[2].parse::<f64>().is_ok() => {
+                        (fields[1], Some(num(fields[2])?))
+                    }
+                    3 => (fields[2], None),
+                    2 if !needs_value => (fields[1], None),
+                    _ => return err(line_no, "malformed bound"),
+                };
+                let Some(&j) = col_ids.get(col) else {
+                    return err(line_no, format!("unknown column {}", col));
+                };
+                let info = &mb.variables()[j];
+                let (lb, ub, integer) = (info.lb, info.ub, info.integer);
+                let v = value.unwrap_or(0.0);
+                let (lb, ub, integer) = match t.as_str() {
+                    "UP" => (lb, v, integer),
+                    "LO" => (v, ub, integer),
+                    "FX" => (v, v, integer),
+                    "FR" => (f64::NEG_INFINITY, f64::INFINITY, integer),
+                    "MI" => (f64::NEG_INFINITY, ub, integer),
+                    "PL" => (lb, f64::INFINITY, integer),
+                    "BV" => (0.0, 1.0, true),
+                    "LI" => (v, ub, true),
+                    "UI" => (lb, v, true),
+                    _ => return err(line_no, format!("unsupported bound type {}", fields[0])),
+                };
+                mb.set_bounds(VariableId(j), lb, ub);
+                mb.set_integer(VariableId(j), integer);
+            }
+        }
+    }
+    mb.objective = Expression::from_terms(mb.objective.constant, obj_terms);
+    for (t, terms, rhs, range) in rows {
+        let (lo, hi) = match (t, range) {
+            ('N', _) => (f64::NEG_INFINITY, f64::INFINITY),
+            ('L', None) => (f64::NEG_INFINITY, rhs),
+            ('G', None) => (rhs, f64::INFINITY),
+            ('L', Some(r)) => (rhs - r.abs(), rhs),
+            ('G', Some(r)) => (rhs, rhs + r.abs()),
+            (_, Some(r)) if r < 0.0 => (rhs + r, rhs),
+            (_, Some(r)) => (rhs, rhs + r),
+            (_, None) => (rhs, rhs),
+        };
+        mb.add_constraint(Constraint {
+            expr: Expression::from_terms(0.0, terms),
+            lo,
+            hi,
+        });
+    }
+    Ok(mb)
+}
+
+fn parse_sense(s: &str, line: usize) -> Result<Sense, ParseError> {
+    match s.to_uppercase().as_str() {
+        "MIN" | "MINIMIZE" => Ok(Sense::Minimize),
+        "MAX" | "MAXIMIZE" => Ok(Sense::Maximize),
+        _ => err(line, format!("unknown objective sense {}", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::*;
+
+    fn sample() -> ModelBuilder {
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, 4.0, true, "x".to_string());
+        let y = mb.add_var(-2.5, f64::INFINITY, false, "y".to_string());
+        let z = mb.add_var(f64::NEG_INFINITY, f64::INFINITY, false, String::new());
+        let w = mb.add_var(f64::NEG_INFINITY, 3.0, true, "w w".to_string());
+        let u = mb.add_var(1.0, 1.0, false, "u".to_string());
+        mb.add_var(0.0, f64::INFINITY, false, "unused".to_string());
+        mb.add_constraint((3 * x + 2 * y - z) << 18);
+        mb.add_constraint((x - 1e-7 * w + 2) >> -3);
+        mb.add_constraint((y + z + w).eq(1.5));
+        mb.add_constraint((x + u - 2 * y).between(-1.0, 2.0));
+        mb.maximize(3 * x + 5 * y - 0.5 * z + 7);
+        mb
+    }
+
+    fn assert_same(a: &ModelBuilder, b: &ModelBuilder, names: &[&str]) {
+        assert_eq!(a.variables().len(), b.variables().len());
+        assert_eq!(a.constraints().len(), b.constraints().len());
+        assert_eq!(a.sense, b.sense);
+        // position of each variable of a in b
+        let map: Vec<usize> = names.iter().map(|n| b.var_by_name(n).unwrap().0).collect();
+        for (i, v) in a.variables().iter().enumerate() {
+            let u = &b.variables()[map[i]];
+            assert_eq!((v.lb, v.ub, v.integer), (u.lb, u.ub, u.integer));
+        }
+        let mut rng = Splitmix64::from_u64(1);
+        for _ in 0..10 {
+            let va: Vec<f64> = (0..a.variables().len())
+                .map(|_| rng.next01() * 10.0 - 5.0)
+                .collect();
+            let mut vb = vec![0.0; va.len()];
+            for (i, &j) in map.iter().enumerate() {
+                vb[j] = va[i];
+            }
+            let close = |x: f64, y: f64| (x - y).abs() < 1e-9;
+            assert!(close(a.objective.eval(&va), b.objective.eval(&vb)));
+            for (ca, cb) in a.constraints().iter().zip(b.constraints().iter()) {
+                // compares the linear parts, constants are moved to the row bounds
+                let ea = ca.expr.eval(&va) - ca.expr.constant;
+                let eb = cb.expr.eval(&vb) - cb.expr.constant;
+                assert!(close(ea, eb));
+                assert_eq!(ca.row_bounds(), cb.row_bounds());
+            }
+        }
+    }
+
+    #[test]
+    fn lp_round_trip() {
+        let mb = sample();
+        let mut s = String::new();
+        write_lp(&mb, &mut s).unwrap();
+        let read = read_lp(&s).unwrap();
+        assert_same(&mb, &read, &["x", "y", "x2", "w_w", "u", "unused"]);
+    }
+
+    #[test]
+    fn mps_round_trip() {
+        let mb = sample();
+        let mut s = String::new();
+        write_mps(&mb, &mut s).unwrap();
+        let read = read_mps(&s).unwrap();
+        assert_same(&mb, &read, &["x", "y", "x2", "w_w", "u", "unused"]);
+        for (i, v) in read.variables().iter().enumerate() {
+            assert_eq!(read.var_by_name(&v.name), Some(VariableId(i)));
+        }
+    }
+
+    #[test]
+    fn read_lp_file() {
+        let s = r"\ a comment
+Maximize
+ obj: 2 a + 3b_1 - c
+Subject To
+ first: a + b_1 <= 4
+ -1 <= a - c <= 1.5e0
+ a + b_1 + c >= 1
+Bounds
+ b_1 <= 3
+ c free
+Binaries
+ a
+End";
+        let mb = read_lp(s).unwrap();
+        assert_eq!(mb.sense, Sense::Maximize);
+        assert_eq!(mb.variables().len(), 3);
+        assert_eq!(mb.constraints().len(), 3);
+        let a = &mb.variables()[0];
+        assert_eq!((a.lb, a.ub, a.integer), (0.0, 1.0, true));
+        let c = &mb.variables()[2];
+        assert_eq!((c.lb, c.ub), (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(mb.constraints()[1].row_bounds(), (-1.0, 1.5));
+        assert_eq!(mb.objective.eval(&[1.0, 1.0, 1.0]), 4.0);
+        assert!(read_lp("Minimize\n obj: x\nSubject To\n c: x <=").is_err());
+    }
+}
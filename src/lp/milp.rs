@@ -0,0 +1,349 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::core::*;
+use crate::lp::model::*;
+use crate::lp::simplex::*;
+
+/// tolerance on bounds, constraints and integrality
+const FEAS_TOL: f64 = 1e-6;
+/// minimum improvement over the incumbent for a node not to be pruned
+const CUTOFF_TOL: f64 = 1e-9;
+
+/// objective value of a MILP, as the model objective multiplied by the sign of the model sense
+/// so that it is always minimized, unfeasible is +inf and unbounded is -inf
+#[derive(Clone, Copy, Debug)]
+pub struct MilpObj(pub f64);
+impl PartialEq for MilpObj {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for MilpObj {}
+impl PartialOrd for MilpObj {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for MilpObj {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+impl From<MilpObj> for f64 {
+    fn from(value: MilpObj) -> Self {
+        value.0
+    }
+}
+impl Objective for MilpObj {
+    fn unfeas() -> Self {
+        Self(f64::INFINITY)
+    }
+    fn unbounded() -> Self {
+        Self(f64::NEG_INFINITY)
+    }
+    fn is_feas(&self) -> bool {
+        self.0 < f64::INFINITY
+    }
+    fn is_bounded(&self) -> bool {
+        self.0 > f64::NEG_INFINITY
+    }
+}
+
+/// optimization of the objective of `model` over its variables and constraints,
+/// a solution is the value of each variable indexed by VariableId
+#[derive(Clone, Debug)]
+pub struct MilpProblem {
+    pub model: ModelBuilder,
+}
+impl MilpProblem {
+    fn min_obj(&self, sol: &[f64]) -> MilpObj {
+        MilpObj(self.model.sense.sign() * self.model.objective.eval(sol))
+    }
+}
+impl From<ModelBuilder> for MilpProblem {
+    fn from(model: ModelBuilder) -> Self {
+        Self { model }
+    }
+}
+impl Problem for MilpProblem {
+    type Sol = Vec<f64>;
+    type Obj = MilpObj;
+    fn obj(&self, sol: &Self::Sol) -> Self::Obj {
+        if self.is_feasible(sol) {
+            self.min_obj(sol)
+        } else {
+            MilpObj::unfeas()
+        }
+    }
+    fn is_feasible(&self, sol: &Self::Sol) -> bool {
+        sol.len() == self.model.variables().len()
+            && self.model.variables().iter().zip(sol).all(|(v, &x)| {
+                x >= v.lb - FEAS_TOL
+                    && x <= v.ub + FEAS_TOL
+                    && (!v.integer || (x - x.round()).abs() <= FEAS_TOL)
+            })
+            && self
+                .model
+                .constraints()
+                .iter()
+                .all(|c| c.is_satisfied(sol, FEAS_TOL))
+    }
+}
+impl Reduction<MilpProblem> for MilpProblem {
+    fn reduce_from(p: &MilpProblem) -> Self {
+        p.clone()
+    }
+    fn lift_solution_to(&self, sol: Self::Sol) -> Vec<f64> {
+        sol
+    }
+    fn lift_obj_to(&self, obj: Self::Obj) -> MilpObj {
+        obj
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeSelection {
+    /// explores the last created node first, finds incumbents quickly and keeps few open nodes
+    DepthFirst,
+    /// explores the node with the lowest LP bound first, raises the dual bound quickly
+    BestBound,
+}
+
+/// node of the branch and bound tree
+struct Node {
+    id: usize,
+    parent: usize,
+    /// bounds changed with respect to the root as (variable, lb, ub), later entries override earlier ones
+    bounds: Vec<(usize, f64, f64)>,
+    /// LP objective of the parent, a lower bound for the node
+    bound: f64,
+    /// optimal basis of the parent, to warm start the dual simplex
+    basis: Option<Rc<Basis>>,
+}
+
+/// LP based branch and bound that branches on the most fractional integer variable,
+/// re-solving each node with the dual simplex from the basis of its parent.
+/// unbounded relaxations are not supported: the search stops at the first one and reports an
+/// unbounded dual bound, even though the integer problem may be infeasible or bounded
+#[derive(Clone)]
+pub struct BranchAndBound {
+    node_selection: NodeSelection,
+}
+impl BranchAndBound {
+    pub fn new(node_selection: NodeSelection) -> Self {
+        Self { node_selection }
+    }
+    fn pop(&self, open: &mut Vec<Node>) -> Option<Node> {
+        match self.node_selection {
+            NodeSelection::DepthFirst => open.pop(),
+            NodeSelection::BestBound => {
+                let i = (0..open.len()).min_by(|&a, &b| open[a].bound.total_cmp(&open[b].bound))?;
+                Some(open.swap_remove(i))
+            }
+        }
+    }
+}
+impl Default for BranchAndBound {
+    fn default() -> Self {
+        Self::new(NodeSelection::DepthFirst)
+    }
+}
+impl Solver<MilpProblem> for BranchAndBound {
+    fn solve<SK: SolutionKeeper<MilpProblem>, S: stop_condition::StopCondition<MilpObj>>(
+        &mut self,
+        p: MilpProblem,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let vars = &p.model.variables();
+        let sign = p.model.sense.sign();
+        let mut simplex = Simplex::new(&p.model);
+        let root_bounds: Vec<(f64, f64)> = vars
+            .iter()
+            .map(|v| {
+                if v.integer {
+                    (v.lb.ceil(), v.ub.floor())
+                } else {
+                    (v.lb, v.ub)
+                }
+            })
+            .collect();
+        for (j, &(lb, ub)) in root_bounds.iter().enumerate() {
+            if vars[j].integer {
+                simplex.set_bounds(VariableId(j), lb, ub);
+            }
+        }
+        let mut open = vec![Node {
+            id: 0,
+            parent: usize::MAX,
+            bounds: vec![],
+            bound: f64::NEG_INFINITY,
+            basis: None,
+        }];
+        let mut next_id = 1;
+        let mut last_solved = usize::MAX;
+        // variables whose bounds in the simplex differ from the root ones
+        let mut changed = Vec::<usize>::new();
+        let mut dual_bound = f64::NEG_INFINITY;
+        loop {
+            if stop.stop(sk.best_obj(), MilpObj(dual_bound)) {
+                break;
+            }
+            let Some(node) = self.pop(&mut open) else {
+                break;
+            };
+            if node.bound >= sk.best_obj().0 - CUTOFF_TOL {
+                continue;
+            }
+            sk.iter();
+
+            for &j in changed.iter() {
+                simplex.set_bounds(VariableId(j), root_bounds[j].0, root_bounds[j].1);
+            }
+            changed.clear();
+            for &(j, lb, ub) in node.bounds.iter() {
+                simplex.set_bounds(VariableId(j), lb, ub);
+                changed.push(j);
+            }
+            if node.parent != last_solved {
+                if let Some(basis) = node.basis.as_ref() {
+                    simplex.set_basis(basis);
+                }
+            }
+            let lp = simplex.solve();
+            let lp_obj = sign * lp.obj;
+            last_solved = node.id;
+
+            match lp.status {
+                LpStatus::Infeasible => {}
+                // no bound can be derived from this node, and branching on the values of an
+                // unbounded ray is meaningless
+                LpStatus::Unbounded => {
+                    sk.add_dual_bound(MilpObj::unbounded());
+                    return;
+                }
+                LpStatus::Optimal if lp_obj < sk.best_obj().0 - CUTOFF_TOL => {
+                    let fractional = (0..vars.len())
+                        .filter(|&j| vars[j].integer)
+                        .map(|j| (j, (lp.values[j] - lp.values[j].round()).abs()))
+                        .filter(|&(_, frac)| frac > FEAS_TOL)
+                        .max_by(|a, b| a.1.total_cmp(&b.1));
+                    match fractional {
+                        None => {
+                            let mut sol = lp.values;
+                            for (j, v) in sol.iter_mut().enumerate() {
+                                if vars[j].integer {
+                                    *v = v.round();
+                                }
+                            }
+                            sk.add_solution(&sol, p.min_obj(&sol));
+                        }
+                        Some((j, _)) => {
+                            let v = lp.values[j];
+                            let basis = Rc::new(simplex.basis());
+                            let mut down = node.bounds.clone();
+                            let (lb, ub) = down
+                                .iter()
+                                .rev()
+                                .find(|b| b.0 == j)
+                                .map_or(root_bounds[j], |b| (b.1, b.2));
+                            let mut up = down.clone();
+                            down.push((j, lb, v.floor()));
+                            up.push((j, v.ceil(), ub));
+                            // the child on the side v is rounded to is pushed last to be explored first
+                            let mut children = [down, up];
+                            if v - v.floor() >= 0.5 {
+                                children.reverse();
+                            }
+                            for bounds in children {
+                                open.push(Node {
+                                    id: next_id,
+                                    parent: node.id,
+                                    bounds,
+                                    bound: lp_obj,
+                                    basis: Some(basis.clone()),
+                                });
+                                next_id += 1;
+                            }
+                        }
+                    }
+                }
+                LpStatus::Optimal => {}
+            }
+
+            let db = open.iter().map(|n| n.bound).fold(sk.best_obj().0, f64::min);
+            if db > dual_bound {
+                dual_bound = db;
+                sk.add_dual_bound(MilpObj(db));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Splitmix64::from_u64(42);
+        for _ in 0..30 {
+            let mut model = ModelBuilder::new();
+            let x: Vec<VariableId> = (0..4)
+                .map(|i| model.add_var(0.0, 3.0, true, format!("x{}", i)))
+                .collect();
+            let mut rows = vec![];
+            for _ in 0..3 {
+                let coef: Vec<i64> = (0..4).map(|_| (rng.next_u64() % 7) as i64 - 2).collect();
+                let rhs = (rng.next_u64() % 8) as i64 + 2;
+                let lhs = (0..4).fold(Expression::cons(0.0), |acc, i| acc + coef[i] * x[i]);
+                model.add_constraint(lhs << rhs as f64);
+                rows.push((coef, rhs));
+            }
+            let c: Vec<i64> = (0..4).map(|_| (rng.next_u64() % 9) as i64 - 6).collect();
+            // maximizing -c·x is minimizing c·x
+            model.maximize((0..4).fold(Expression::cons(0.0), |acc, i| acc - c[i] * x[i]));
+
+            let mut best = f64::INFINITY;
+            for code in 0..256 {
+                let v: Vec<i64> = (0..4).map(|i| (code >> (2 * i)) & 3).collect();
+                let ok = rows
+                    .iter()
+                    .all(|(coef, rhs)| (0..4).map(|i| coef[i] * v[i]).sum::<i64>() <= *rhs);
+                if ok {
+                    best = best.min((0..4).map(|i| c[i] * v[i]).sum::<i64>() as f64);
+                }
+            }
+
+            for node_selection in [NodeSelection::DepthFirst, NodeSelection::BestBound] {
+                let p = MilpProblem::from(model.clone());
+                let mut sk = SimpleSolutionKeeper::<MilpProblem>::default();
+                let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+                BranchAndBound::new(node_selection).solve(p.clone(), &mut sk, stop);
+                let obj = sk.best_obj().0;
+                if best.is_infinite() {
+                    assert!(!sk.best_obj().is_feas());
+                } else {
+                    assert!((obj - best).abs() < 1e-6, "{} != {}", obj, best);
+                    let (sol, _) = sk.best_solution().unwrap();
+                    assert!(p.is_feasible(&sol));
+                }
+                assert_eq!(sk.dual_bound, sk.best_obj());
+            }
+        }
+    }
+
+    #[test]
+    fn unbounded_relaxation() {
+        let mut model = ModelBuilder::new();
+        let x = model.add_var(0.0, f64::INFINITY, true, "x".to_string());
+        let y = model.add_var(0.0, f64::INFINITY, true, "y".to_string());
+        model.add_constraint((x - y) << 1);
+        model.maximize(x + y);
+        let mut sk = SimpleSolutionKeeper::<MilpProblem>::default();
+        let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+        BranchAndBound::default().solve(MilpProblem::from(model), &mut sk, stop);
+        assert_eq!(sk.dual_bound, MilpObj::unbounded());
+    }
+}
@@ -1 +1,4 @@
+pub mod io;
+pub mod milp;
 pub mod model;
+pub mod simplex;
@@ -8,9 +8,9 @@ use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Shl, Shr, Sub, SubAssign};
 #[derive(Clone)]
 pub struct Expression {
     /// the constant part of the sum
-    constant: f64,
+    pub(crate) constant: f64,
     /// variables ids with their multiplier, sorted by Id
-    variables: Vec<(usize, f64)>,
+    pub(crate) variables: Vec<(usize, f64)>,
 }
 impl Expression {
     pub fn cons(val: f64) -> Self {
@@ -19,6 +19,65 @@ impl Expression {
             variables: vec![],
         }
     }
+    /// builds an expression from unsorted terms, summing the multipliers of repeated variables
+    pub(crate) fn from_terms(constant: f64, mut terms: Vec<(usize, f64)>) -> Self {
+        terms.sort_by_key(|t| t.0);
+        let mut variables = Vec::<(usize, f64)>::with_capacity(terms.len());
+        for (id, mul) in terms {
+            match variables.last_mut() {
+                Some(last) if last.0 == id => last.1 += mul,
+                _ => variables.push((id, mul)),
+            }
+        }
+        variables.retain(|v| v.1 != 0.0);
+        Self {
+            constant,
+            variables,
+        }
+    }
+    /// represents =
+    pub fn eq<T: Into<Expression>>(&self, rhs: T) -> Constraint {
+        Constraint {
+            expr: self - rhs,
+            lo: 0.0,
+            hi: 0.0,
+        }
+    }
+    /// represents lo <= self <= hi
+    pub fn between(&self, lo: f64, hi: f64) -> Constraint {
+        Constraint {
+            expr: self.clone(),
+            lo,
+            hi,
+        }
+    }
+    /// value of the expression given the value of each variable, indexed by VariableId
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        self.constant
+            + self
+                .variables
+                .iter()
+                .map(|&(i, mul)| mul * values[i])
+                .sum::<f64>()
+    }
+    /// multiplier of the variable, 0 if not present
+    pub fn coefficient(&self, vid: VariableId) -> f64 {
+        match self.variables.binary_search_by_key(&vid.0, |v| v.0) {
+            Ok(i) => self.variables[i].1,
+            Err(_) => 0.0,
+        }
+    }
+    /// sets the multiplier of the variable, removing it if 0
+    pub fn set_coefficient(&mut self, vid: VariableId, mul: f64) {
+        match self.variables.binary_search_by_key(&vid.0, |v| v.0) {
+            Ok(i) if mul == 0.0 => {
+                self.variables.remove(i);
+            }
+            Ok(i) => self.variables[i].1 = mul,
+            Err(i) if mul != 0.0 => self.variables.insert(i, (vid.0, mul)),
+            Err(_) => {}
+        }
+    }
 }
 impl From<VariableId> for Expression {
     fn from(vid: VariableId) -> Self {
@@ -236,7 +295,58 @@ impl Neg for VariableId {
 
 /// corresponds to: sum of constant and all variables in the expression <= 0
 #[derive(Clone)]
-pub struct Inequality(Expression);
+pub struct Inequality(pub(crate) Expression);
+
+/// corresponds to: lo <= sum of constant and all variables in the expression <= hi,
+/// an equality if lo == hi
+#[derive(Clone)]
+pub struct Constraint {
+    pub(crate) expr: Expression,
+    pub(crate) lo: f64,
+    pub(crate) hi: f64,
+}
+impl From<Inequality> for Constraint {
+    fn from(ineq: Inequality) -> Self {
+        Self {
+            expr: ineq.0,
+            lo: f64::NEG_INFINITY,
+            hi: 0.0,
+        }
+    }
+}
+impl Constraint {
+    pub fn expr(&self) -> &Expression {
+        &self.expr
+    }
+    /// (lo, hi) bounds on the whole expression, constant included
+    pub fn bounds(&self) -> (f64, f64) {
+        (self.lo, self.hi)
+    }
+    /// bounds on the variable part of the expression, with the constant moved to the bounds
+    pub(crate) fn row_bounds(&self) -> (f64, f64) {
+        (self.lo - self.expr.constant, self.hi - self.expr.constant)
+    }
+    pub fn is_satisfied(&self, values: &[f64], tol: f64) -> bool {
+        let v = self.expr.eval(values);
+        v >= self.lo - tol && v <= self.hi + tol
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sense {
+    Minimize,
+    Maximize,
+}
+impl Sense {
+    /// 1 for minimization and -1 for maximization,
+    /// multiplying the objective by it gives an equivalent minimization
+    pub fn sign(self) -> f64 {
+        match self {
+            Sense::Minimize => 1.0,
+            Sense::Maximize => -1.0,
+        }
+    }
+}
 
 /// information on a single variable for the LP model
 #[derive(Clone)]
@@ -249,13 +359,21 @@ pub struct VariableInfo {
     pub integer: bool,
     /// variable name
     pub name: String,
+    /// removed variables keep their id, they are fixed to 0 and appear in no expression
+    pub removed: bool,
 }
 #[derive(Clone)]
 pub struct ModelBuilder {
-    pub variables: Vec<VariableInfo>,
-    pub constraints: Vec<Inequality>,
+    /// indexed by VariableId, removed ones are kept
+    variables: Vec<VariableInfo>,
+    /// constraints in order of creation, removed ones are not present
+    constraints: Vec<Constraint>,
+    pub objective: Expression,
+    pub sense: Sense,
     next_variable_id: usize,
     next_constraint_id: usize,
+    /// id of each constraint, sorted as constraints are only appended
+    constraint_ids: Vec<usize>,
 }
 impl Default for ModelBuilder {
     fn default() -> Self {
@@ -263,10 +381,12 @@ impl Default for ModelBuilder {
     }
 }
 
-// TODO: use variable and constraint id to remove variable/constraints,
-// add solving interface
+/// index of the variable in ModelBuilder::variables and in solutions,
+/// ids are never reused so they stay valid after removals
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct VariableId(usize);
+pub struct VariableId(pub(crate) usize);
+/// stable identifier of a constraint, its position in ModelBuilder::constraints()
+/// changes when previous constraints are removed
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ConstraintId(usize);
 impl ModelBuilder {
@@ -274,8 +394,11 @@ impl ModelBuilder {
         Self {
             variables: vec![],
             constraints: vec![],
+            objective: Expression::cons(0.0),
+            sense: Sense::Minimize,
             next_variable_id: 0,
             next_constraint_id: 0,
+            constraint_ids: vec![],
         }
     }
     pub fn add_var(&mut self, lb: f64, ub: f64, integer: bool, name: String) -> VariableId {
@@ -284,17 +407,125 @@ impl ModelBuilder {
             ub,
             integer,
             name,
+            removed: false,
         });
         let ret = VariableId(self.next_variable_id);
         self.next_variable_id += 1;
         ret
     }
-    pub fn add_constraint(&mut self, constraint: Inequality) -> ConstraintId {
-        self.constraints.push(constraint);
+    pub fn add_constraint<C: Into<Constraint>>(&mut self, constraint: C) -> ConstraintId {
+        self.constraints.push(constraint.into());
+        self.constraint_ids.push(self.next_constraint_id);
         let ret = ConstraintId(self.next_constraint_id);
         self.next_constraint_id += 1;
         ret
     }
+    /// position of the constraint in self.constraints, None if removed
+    pub fn constraint_index(&self, cid: ConstraintId) -> Option<usize> {
+        self.constraint_ids.binary_search(&cid.0).ok()
+    }
+    pub fn constraint(&self, cid: ConstraintId) -> Option<&Constraint> {
+        self.constraint_index(cid).map(|i| &self.constraints[i])
+    }
+    /// indexed by VariableId, removed ones are kept
+    pub fn variables(&self) -> &[VariableInfo] {
+        &self.variables
+    }
+    /// constraints in order of creation, removed ones are not present
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+    /// ids of the constraints, in the same order as self.constraints()
+    pub fn constraint_ids(&self) -> impl Iterator<Item = ConstraintId> + '_ {
+        self.constraint_ids.iter().map(|&i| ConstraintId(i))
+    }
+    /// removes the constraint, returns it or None if it was already removed
+    pub fn remove_constraint(&mut self, cid: ConstraintId) -> Option<Constraint> {
+        let i = self.constraint_index(cid)?;
+        self.constraint_ids.remove(i);
+        Some(self.constraints.remove(i))
+    }
+    /// replaces the constraint keeping its id, returns the previous one or None if it was removed
+    pub fn replace_constraint<C: Into<Constraint>>(
+        &mut self,
+        cid: ConstraintId,
+        constraint: C,
+    ) -> Option<Constraint> {
+        let i = self.constraint_index(cid)?;
+        Some(std::mem::replace(
+            &mut self.constraints[i],
+            constraint.into(),
+        ))
+    }
+    /// sets the multiplier of the variable in the constraint, returns false if the constraint was removed
+    pub fn set_coefficient(&mut self, cid: ConstraintId, vid: VariableId, mul: f64) -> bool {
+        assert!(
+            !self.variables[vid.0].removed,
+            "variable {:?} was removed",
+            vid
+        );
+        match self.constraint_index(cid) {
+            Some(i) => {
+                self.constraints[i].expr.set_coefficient(vid, mul);
+                true
+            }
+            None => false,
+        }
+    }
+    /// sets the multiplier of the variable in the objective
+    pub fn set_objective_coefficient(&mut self, vid: VariableId, mul: f64) {
+        assert!(
+            !self.variables[vid.0].removed,
+            "variable {:?} was removed",
+            vid
+        );
+        self.objective.set_coefficient(vid, mul);
+    }
+    pub fn set_bounds(&mut self, vid: VariableId, lb: f64, ub: f64) {
+        let v = &mut self.variables[vid.0];
+        assert!(!v.removed, "variable {:?} was removed", vid);
+        v.lb = lb;
+        v.ub = ub;
+    }
+    pub fn set_integer(&mut self, vid: VariableId, integer: bool) {
+        let v = &mut self.variables[vid.0];
+        assert!(!v.removed, "variable {:?} was removed", vid);
+        v.integer = integer;
+    }
+    /// removes the variable from the objective and all constraints, the other ids are unchanged,
+    /// returns false if it was already removed
+    pub fn remove_var(&mut self, vid: VariableId) -> bool {
+        let v = &mut self.variables[vid.0];
+        if v.removed {
+            return false;
+        }
+        v.removed = true;
+        v.lb = 0.0;
+        v.ub = 0.0;
+        v.integer = false;
+        self.objective.set_coefficient(vid, 0.0);
+        for c in self.constraints.iter_mut() {
+            c.expr.set_coefficient(vid, 0.0);
+        }
+        true
+    }
+    /// returns the first variable with the given name
+    pub fn var_by_name(&self, name: &str) -> Option<VariableId> {
+        self.variables
+            .iter()
+            .position(|v| !v.removed && v.name == name)
+            .map(VariableId)
+    }
+    pub fn set_objective<T: Into<Expression>>(&mut self, sense: Sense, objective: T) {
+        self.sense = sense;
+        self.objective = objective.into();
+    }
+    pub fn minimize<T: Into<Expression>>(&mut self, objective: T) {
+        self.set_objective(Sense::Minimize, objective);
+    }
+    pub fn maximize<T: Into<Expression>>(&mut self, objective: T) {
+        self.set_objective(Sense::Maximize, objective);
+    }
     pub fn fmt_var(&self, id: usize, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         if self.variables[id].name.is_empty() {
             write!(f, "var[{}]", id)
@@ -329,11 +560,33 @@ impl ModelBuilder {
         self.fmt_expr(&ineq.0, f)?;
         write!(f, " ≤ 0")
     }
+    pub fn fmt_constraint(&self, c: &Constraint, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        if c.lo == c.hi {
+            self.fmt_expr(&c.expr, f)?;
+            write!(f, " = {}", c.lo)
+        } else if c.lo == f64::NEG_INFINITY {
+            self.fmt_expr(&c.expr, f)?;
+            write!(f, " ≤ {}", c.hi)
+        } else if c.hi == f64::INFINITY {
+            self.fmt_expr(&c.expr, f)?;
+            write!(f, " ≥ {}", c.lo)
+        } else {
+            write!(f, "{} ≤", c.lo)?;
+            self.fmt_expr(&c.expr, f)?;
+            write!(f, " ≤ {}", c.hi)
+        }
+    }
 }
 impl Debug for ModelBuilder {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        for ineq in self.constraints.iter() {
-            self.fmt_ineq(ineq, f)?;
+        match self.sense {
+            Sense::Minimize => write!(f, "min")?,
+            Sense::Maximize => write!(f, "max")?,
+        }
+        self.fmt_expr(&self.objective, f)?;
+        writeln!(f)?;
+        for c in self.constraints.iter() {
+            self.fmt_constraint(c, f)?;
             writeln!(f)?;
         }
         Ok(())
@@ -359,4 +612,76 @@ mod tests {
         );
         eprintln!("{:?}", mb);
     }
+
+    #[test]
+    fn sense_equality_and_range() {
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, 1.0, false, "x".to_string());
+        let y = mb.add_var(0.0, 1.0, false, "y".to_string());
+        let eq = mb.add_constraint((x + y + 1).eq(2));
+        let range = mb.add_constraint((x - y).between(-1.0, 0.5));
+        assert_eq!(mb.sense, Sense::Minimize);
+        mb.maximize(x + 2 * y);
+        assert_eq!(mb.sense, Sense::Maximize);
+        assert_eq!(mb.sense.sign(), -1.0);
+
+        let eq = mb.constraint(eq).unwrap();
+        assert_eq!(eq.row_bounds(), (1.0, 1.0));
+        assert!(eq.is_satisfied(&[0.25, 0.75], 1e-9));
+        assert!(!eq.is_satisfied(&[0.25, 0.5], 1e-9));
+        let range = mb.constraint(range).unwrap();
+        assert_eq!(range.row_bounds(), (-1.0, 0.5));
+        assert!(range.is_satisfied(&[0.0, 1.0], 1e-9));
+        assert!(range.is_satisfied(&[0.5, 0.0], 1e-9));
+        assert!(!range.is_satisfied(&[1.0, 0.0], 1e-9));
+        eprintln!("{:?}", mb);
+    }
+
+    #[test]
+    fn edit_by_id() {
+        let mut mb = ModelBuilder::new();
+        let x: Vec<VariableId> = (0..4)
+            .map(|i| mb.add_var(0.0, 1.0, false, format!("x{}", i)))
+            .collect();
+        let c: Vec<ConstraintId> = (0..4)
+            .map(|i| mb.add_constraint((x[i] + x[(i + 1) % 4]) << 1))
+            .collect();
+        mb.minimize(x[0] + x[1] + x[2] + x[3]);
+
+        assert!(mb.remove_constraint(c[1]).is_some());
+        assert!(mb.remove_constraint(c[1]).is_none());
+        assert_eq!(mb.constraint_index(c[2]), Some(1));
+        assert_eq!(
+            mb.constraint_ids().collect::<Vec<_>>(),
+            vec![c[0], c[2], c[3]]
+        );
+        let c4 = mb.add_constraint(Expression::from(x[0]) >> 0.5);
+        assert_eq!(mb.constraint_index(c4), Some(3));
+
+        assert!(mb.remove_var(x[1]));
+        assert!(!mb.remove_var(x[1]));
+        assert!(mb.variables()[1].removed);
+        assert_eq!(mb.constraint(c[0]).unwrap().expr().coefficient(x[1]), 0.0);
+        assert_eq!(
+            mb.constraint(c[0]).unwrap().bounds(),
+            (f64::NEG_INFINITY, 0.0)
+        );
+        assert_eq!(mb.objective.coefficient(x[1]), 0.0);
+        assert_eq!(mb.var_by_name("x2"), Some(x[2]));
+        assert_eq!(mb.var_by_name("x1"), None);
+
+        assert!(mb.set_coefficient(c[2], x[3], 2.0));
+        assert!(!mb.set_coefficient(c[1], x[3], 2.0));
+        mb.set_objective_coefficient(x[0], 0.0);
+        mb.set_bounds(x[3], 0.5, 0.75);
+        let old = mb.replace_constraint(c[3], (x[3] - x[0]).eq(0.0)).unwrap();
+        assert_eq!(old.expr().coefficient(x[3]), 1.0);
+
+        let values = [0.5, 0.0, 0.0, 0.5];
+        assert!(mb
+            .constraints()
+            .iter()
+            .all(|c| c.is_satisfied(&values, 1e-9)));
+        assert_eq!(mb.objective.eval(&values), 0.5);
+    }
 }
@@ -0,0 +1,768 @@
+use crate::lp::model::*;
+
+/// tolerance on bound violations
+const PRIMAL_TOL: f64 = 1e-9;
+/// tolerance on reduced costs
+const DUAL_TOL: f64 = 1e-9;
+/// smallest absolute value accepted as a pivot element
+const PIVOT_TOL: f64 = 1e-9;
+/// number of pivots after which the basis inverse is recomputed from scratch
+const REFACTOR_PERIOD: usize = 64;
+/// number of consecutive degenerate pivots after which Bland's rule is used
+const DEGENERATE_LIMIT: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LpStatus {
+    Optimal,
+    Infeasible,
+    Unbounded,
+}
+
+/// result of solving a linear program
+#[derive(Clone, Debug)]
+pub struct LpSolution {
+    pub status: LpStatus,
+    /// objective value in the sense of the model,
+    /// the worst possible value if infeasible and the best possible one if unbounded
+    pub obj: f64,
+    /// value of each variable, indexed by VariableId
+    pub values: Vec<f64>,
+    /// number of simplex iterations done by the solve
+    pub iterations: usize,
+}
+impl LpSolution {
+    pub fn value(&self, vid: VariableId) -> f64 {
+        self.values[vid.0]
+    }
+}
+
+/// position of a column with respect to the basis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColState {
+    Basic,
+    /// nonbasic at its lower bound
+    Lower,
+    /// nonbasic at its upper bound
+    Upper,
+    /// nonbasic free variable fixed at 0
+    Zero,
+}
+
+/// snapshot of a simplex basis,
+/// keep it alongside the ModelBuilder to warm start the simplex after editing the model
+#[derive(Clone, Debug)]
+pub struct Basis {
+    /// number of structural columns of the model the basis comes from
+    n: usize,
+    state: Vec<ColState>,
+    basis: Vec<usize>,
+}
+
+/// bounded-variable primal simplex that optimizes the objective of a ModelBuilder,
+/// keeps the constraint matrix as sparse columns and the basis inverse as a dense matrix.
+/// every constraint `lo <= a·x <= hi` is stored as the row `a·x - r = 0`
+/// where the logical variable r has bounds [lo, hi]
+#[derive(Clone)]
+pub struct Simplex {
+    /// number of structural columns, the logical column of row i is n + i
+    n: usize,
+    /// number of rows
+    m: usize,
+    /// sign of the model sense, costs are stored for the equivalent minimization
+    sign: f64,
+    /// sparse columns as (row, coefficient)
+    cols: Vec<Vec<(usize, f64)>>,
+    lb: Vec<f64>,
+    ub: Vec<f64>,
+    cost: Vec<f64>,
+    obj_constant: f64,
+    /// current value of every column
+    x: Vec<f64>,
+    state: Vec<ColState>,
+    /// column that is basic in each basis position
+    basis: Vec<usize>,
+    /// inverse of the basis matrix, rows indexed by basis position
+    binv: Vec<Vec<f64>>,
+    /// pivots done since the last refactorization
+    pivots: usize,
+    /// iterations done by the current solve
+    iterations: usize,
+}
+impl Simplex {
+    pub fn new(mb: &ModelBuilder) -> Self {
+        let n = mb.variables().len();
+        let sign = mb.sense.sign();
+        let mut s = Self {
+            n,
+            m: 0,
+            sign,
+            cols: vec![vec![]; n],
+            lb: mb.variables().iter().map(|v| v.lb).collect(),
+            ub: mb.variables().iter().map(|v| v.ub).collect(),
+            cost: vec![0.0; n],
+            obj_constant: sign * mb.objective.constant,
+            x: vec![0.0; n],
+            state: vec![ColState::Lower; n],
+            basis: vec![],
+            binv: vec![],
+            pivots: 0,
+            iterations: 0,
+        };
+        for &(j, c) in mb.objective.variables.iter() {
+            s.cost[j] = sign * c;
+        }
+        for j in 0..n {
+            s.state[j] = s.nonbasic_state(j);
+            s.x[j] = s.nonbasic_value(j);
+        }
+        for c in mb.constraints().iter() {
+            let (lo, hi) = c.row_bounds();
+            s.push_row(&c.expr.variables, lo, hi);
+        }
+        s
+    }
+
+    /// solves the problem starting from the current basis,
+    /// uses the dual simplex if the basis is dual feasible (e.g. after `set_bounds` or `add_constraint`
+    /// on an optimal basis) and the primal simplex otherwise
+    pub fn solve(&mut self) -> LpSolution {
+        self.iterations = 0;
+        let empty_bound = (0..self.cols.len()).any(|j| self.lb[j] > self.ub[j] + PRIMAL_TOL);
+        let status = if empty_bound || self.dual() == Some(LpStatus::Infeasible) {
+            LpStatus::Infeasible
+        } else {
+            self.primal()
+        };
+        self.solution(status)
+    }
+
+    /// changes the bounds of a variable, keeping the current basis
+    pub fn set_bounds(&mut self, vid: VariableId, lb: f64, ub: f64) {
+        let j = vid.0;
+        self.lb[j] = lb;
+        self.ub[j] = ub;
+        if self.state[j] != ColState::Basic {
+            let old = self.x[j];
+            self.fix_nonbasic(j);
+            let delta = self.x[j] - old;
+            if delta != 0.0 {
+                let alpha = self.ftran(j);
+                for (k, a) in alpha.into_iter().enumerate() {
+                    self.x[self.basis[k]] -= delta * a;
+                }
+            }
+        }
+    }
+    /// adds a constraint on the variables already in the model, keeping the current basis
+    pub fn add_constraint<C: Into<Constraint>>(&mut self, constraint: C) {
+        let c = constraint.into();
+        let (lo, hi) = c.row_bounds();
+        self.push_row(&c.expr.variables, lo, hi);
+    }
+    pub fn basis(&self) -> Basis {
+        Basis {
+            n: self.n,
+            state: self.state.clone(),
+            basis: self.basis.clone(),
+        }
+    }
+    /// restores a basis taken from this simplex or from one built on an earlier version of the model,
+    /// variables and constraints added since then start nonbasic and with their logical basic respectively.
+    /// a basis from a bigger model is ignored
+    pub fn set_basis(&mut self, b: &Basis) {
+        let old_m = b.basis.len();
+        if b.n > self.n || old_m > self.m {
+            return;
+        }
+        let map = |j: usize| if j < b.n { j } else { self.n + j - b.n };
+        let mut state = vec![ColState::Lower; self.cols.len()];
+        for (j, &st) in b.state.iter().enumerate() {
+            state[map(j)] = st;
+        }
+        for st in state[b.n..self.n].iter_mut() {
+            *st = ColState::Zero;
+        }
+        self.basis = b.basis.iter().map(|&j| map(j)).collect();
+        self.basis.extend((old_m..self.m).map(|i| self.n + i));
+        for &j in self.basis.iter() {
+            state[j] = ColState::Basic;
+        }
+        self.state = state;
+        for j in 0..self.cols.len() {
+            if self.state[j] != ColState::Basic {
+                self.fix_nonbasic(j);
+            }
+        }
+        self.refactor();
+    }
+
+    fn solution(&self, status: LpStatus) -> LpSolution {
+        let obj = match status {
+            LpStatus::Optimal => {
+                self.obj_constant + (0..self.n).map(|j| self.cost[j] * self.x[j]).sum::<f64>()
+            }
+            LpStatus::Infeasible => f64::INFINITY,
+            LpStatus::Unbounded => f64::NEG_INFINITY,
+        } * self.sign;
+        LpSolution {
+            status,
+            obj,
+            values: self.x[..self.n].to_vec(),
+            iterations: self.iterations,
+        }
+    }
+
+    /// adds the row `lo <= a·x <= hi` with its logical variable in the basis
+    fn push_row(&mut self, terms: &[(usize, f64)], lo: f64, hi: f64) {
+        let row = self.m;
+        let logical = self.cols.len();
+        let mut coef = vec![0.0; self.n];
+        let mut activity = 0.0;
+        for &(j, a) in terms {
+            self.cols[j].push((row, a));
+            coef[j] = a;
+            activity += a * self.x[j];
+        }
+        self.cols.push(vec![(row, -1.0)]);
+        self.lb.push(lo);
+        self.ub.push(hi);
+        self.cost.push(0.0);
+        self.x.push(activity);
+        self.state.push(ColState::Basic);
+        // the new basis is [[B, 0], [a_B, -1]], its inverse is [[B^-1, 0], [a_B·B^-1, -1]]
+        let mut new_row = vec![0.0; self.m + 1];
+        for (k, &b) in self.basis.iter().enumerate() {
+            if b < self.n && coef[b] != 0.0 {
+                for (i, v) in self.binv[k].iter().enumerate() {
+                    new_row[i] += coef[b] * v;
+                }
+            }
+        }
+        new_row[self.m] = -1.0;
+        for r in self.binv.iter_mut() {
+            r.push(0.0);
+        }
+        self.binv.push(new_row);
+        self.basis.push(logical);
+        self.m += 1;
+    }
+
+    fn nonbasic_state(&self, j: usize) -> ColState {
+        if self.lb[j] > f64::NEG_INFINITY {
+            ColState::Lower
+        } else if self.ub[j] < f64::INFINITY {
+            ColState::Upper
+        } else {
+            ColState::Zero
+        }
+    }
+    /// moves a nonbasic column to a valid bound, staying on the same side if possible
+    fn fix_nonbasic(&mut self, j: usize) {
+        self.state[j] = match self.state[j] {
+            ColState::Lower if self.lb[j] > f64::NEG_INFINITY => ColState::Lower,
+            ColState::Upper if self.ub[j] < f64::INFINITY => ColState::Upper,
+            _ => self.nonbasic_state(j),
+        };
+        self.x[j] = self.nonbasic_value(j);
+    }
+    fn nonbasic_value(&self, j: usize) -> f64 {
+        match self.state[j] {
+            ColState::Lower => self.lb[j],
+            ColState::Upper => self.ub[j],
+            _ => 0.0,
+        }
+    }
+
+    /// B^-1 · a_j
+    fn ftran(&self, j: usize) -> Vec<f64> {
+        let mut alpha = vec![0.0; self.m];
+        for (k, r) in self.binv.iter().enumerate() {
+            alpha[k] = self.cols[j].iter().map(|&(i, a)| r[i] * a).sum();
+        }
+        alpha
+    }
+    /// c_B · B^-1
+    fn duals(&self, cost: &[f64]) -> Vec<f64> {
+        let mut y = vec![0.0; self.m];
+        for (k, &b) in self.basis.iter().enumerate() {
+            if cost[b] != 0.0 {
+                for (i, v) in self.binv[k].iter().enumerate() {
+                    y[i] += cost[b] * v;
+                }
+            }
+        }
+        y
+    }
+    fn reduced_cost(&self, j: usize, cost: &[f64], y: &[f64]) -> f64 {
+        cost[j] - self.cols[j].iter().map(|&(i, a)| y[i] * a).sum::<f64>()
+    }
+    /// costs of the phase 1 objective, the sum of bound violations of basic variables
+    fn phase1_cost(&self) -> Option<Vec<f64>> {
+        let mut cost = vec![0.0; self.cols.len()];
+        let mut infeasible = false;
+        for &b in self.basis.iter() {
+            if self.x[b] < self.lb[b] - PRIMAL_TOL {
+                cost[b] = -1.0;
+                infeasible = true;
+            } else if self.x[b] > self.ub[b] + PRIMAL_TOL {
+                cost[b] = 1.0;
+                infeasible = true;
+            }
+        }
+        infeasible.then_some(cost)
+    }
+
+    /// recomputes the values of basic variables from the nonbasic ones
+    fn compute_primal(&mut self) {
+        let mut rhs = vec![0.0; self.m];
+        for j in 0..self.cols.len() {
+            if self.state[j] != ColState::Basic && self.x[j] != 0.0 {
+                for &(i, a) in self.cols[j].iter() {
+                    rhs[i] -= a * self.x[j];
+                }
+            }
+        }
+        for k in 0..self.m {
+            self.x[self.basis[k]] = self.binv[k]
+                .iter()
+                .zip(rhs.iter())
+                .map(|(a, b)| a * b)
+                .sum();
+        }
+    }
+
+    /// recomputes the basis inverse with Gauss-Jordan elimination,
+    /// falls back to the all-logical basis if the current one is singular
+    fn refactor(&mut self) {
+        self.pivots = 0;
+        let m = self.m;
+        let mut a = vec![vec![0.0; 2 * m]; m];
+        for (k, &j) in self.basis.iter().enumerate() {
+            for &(i, v) in self.cols[j].iter() {
+                a[i][k] = v;
+            }
+        }
+        for (i, r) in a.iter_mut().enumerate() {
+            r[m + i] = 1.0;
+        }
+        for k in 0..m {
+            let p = (k..m)
+                .max_by(|&x, &y| a[x][k].abs().total_cmp(&a[y][k].abs()))
+                .unwrap();
+            if a[p][k].abs() <= PIVOT_TOL {
+                self.reset_basis();
+                return;
+            }
+            a.swap(k, p);
+            let inv = 1.0 / a[k][k];
+            for v in a[k].iter_mut() {
+                *v *= inv;
+            }
+            let pivot_row = a[k].clone();
+            for (r, row) in a.iter_mut().enumerate() {
+                let f = row[k];
+                if r != k && f != 0.0 {
+                    for (v, p) in row.iter_mut().zip(pivot_row.iter()) {
+                        *v -= f * p;
+                    }
+                }
+            }
+        }
+        self.binv = a.into_iter().map(|r| r[m..].to_vec()).collect();
+        self.compute_primal();
+    }
+    fn reset_basis(&mut self) {
+        for k in 0..self.m {
+            let b = self.basis[k];
+            if b < self.n {
+                self.state[b] = if self.ub[b] < f64::INFINITY
+                    && (self.lb[b] == f64::NEG_INFINITY
+                        || self.ub[b] - self.x[b] < self.x[b] - self.lb[b])
+                {
+                    ColState::Upper
+                } else {
+                    self.nonbasic_state(b)
+                };
+                self.x[b] = self.nonbasic_value(b);
+            }
+        }
+        for i in 0..self.m {
+            let logical = self.n + i;
+            self.state[logical] = ColState::Basic;
+            self.basis[i] = logical;
+            self.binv[i].iter_mut().for_each(|v| *v = 0.0);
+            self.binv[i][i] = -1.0;
+        }
+        self.compute_primal();
+    }
+
+    /// makes column `enter` basic in position `k`, alpha is B^-1 · a_enter
+    fn pivot(&mut self, k: usize, alpha: &[f64]) {
+        let p = alpha[k];
+        for v in self.binv[k].iter_mut() {
+            *v /= p;
+        }
+        let pivot_row = self.binv[k].clone();
+        for (r, row) in self.binv.iter_mut().enumerate() {
+            let f = alpha[r];
+            if r != k && f != 0.0 {
+                for (v, p) in row.iter_mut().zip(pivot_row.iter()) {
+                    *v -= f * p;
+                }
+            }
+        }
+        self.pivots += 1;
+        if self.pivots >= REFACTOR_PERIOD {
+            self.refactor();
+        }
+    }
+
+    /// primal simplex, phase 1 minimizes the sum of infeasibilities
+    /// and phase 2 the actual objective
+    fn primal(&mut self) -> LpStatus {
+        let mut degenerate = 0;
+        loop {
+            let phase1_cost = self.phase1_cost();
+            let cost = phase1_cost.as_deref().unwrap_or(&self.cost);
+            let y = self.duals(cost);
+            let bland = degenerate >= DEGENERATE_LIMIT;
+            // (column, direction, |reduced cost|)
+            let mut enter: Option<(usize, f64, f64)> = None;
+            for j in 0..self.cols.len() {
+                if self.state[j] == ColState::Basic || self.lb[j] == self.ub[j] {
+                    continue;
+                }
+                let d = self.reduced_cost(j, cost, &y);
+                let dir = match self.state[j] {
+                    ColState::Lower if d < -DUAL_TOL => 1.0,
+                    ColState::Upper if d > DUAL_TOL => -1.0,
+                    ColState::Zero if d.abs() > DUAL_TOL => -d.signum(),
+                    _ => continue,
+                };
+                if enter.is_none_or(|(_, _, best)| d.abs() > best) {
+                    enter = Some((j, dir, d.abs()));
+                    if bland {
+                        break;
+                    }
+                }
+            }
+            let Some((j, dir, _)) = enter else {
+                return if phase1_cost.is_some() {
+                    LpStatus::Infeasible
+                } else {
+                    LpStatus::Optimal
+                };
+            };
+
+            self.iterations += 1;
+            let alpha = self.ftran(j);
+            // a bound flip of the entering column needs no pivot
+            let mut step = self.ub[j] - self.lb[j];
+            let mut leave: Option<(usize, ColState)> = None;
+            for (k, &a) in alpha.iter().enumerate() {
+                let rate = -dir * a;
+                if rate.abs() <= PIVOT_TOL {
+                    continue;
+                }
+                let b = self.basis[k];
+                let v = self.x[b];
+                let (t, st) = if rate < 0.0 {
+                    if v > self.ub[b] + PRIMAL_TOL {
+                        ((v - self.ub[b]) / -rate, ColState::Upper)
+                    } else if self.lb[b] > f64::NEG_INFINITY && v >= self.lb[b] - PRIMAL_TOL {
+                        ((v - self.lb[b]) / -rate, ColState::Lower)
+                    } else {
+                        continue;
+                    }
+                } else if v < self.lb[b] - PRIMAL_TOL {
+                    ((self.lb[b] - v) / rate, ColState::Lower)
+                } else if self.ub[b] < f64::INFINITY && v <= self.ub[b] + PRIMAL_TOL {
+                    ((self.ub[b] - v) / rate, ColState::Upper)
+                } else {
+                    continue;
+                };
+                let t = t.max(0.0);
+                let better = match leave {
+                    _ if t < step => true,
+                    Some((l, _)) if t == step => {
+                        if bland {
+                            b < self.basis[l]
+                        } else {
+                            a.abs() > alpha[l].abs()
+                        }
+                    }
+                    _ => false,
+                };
+                if better {
+                    step = t;
+                    leave = Some((k, st));
+                }
+            }
+            if step == f64::INFINITY {
+                return if phase1_cost.is_some() {
+                    // cannot happen in exact arithmetic, the infeasibility is bounded below
+                    LpStatus::Infeasible
+                } else {
+                    LpStatus::Unbounded
+                };
+            }
+
+            let delta = dir * step;
+            self.x[j] += delta;
+            for (k, &a) in alpha.iter().enumerate() {
+                self.x[self.basis[k]] -= delta * a;
+            }
+            match leave {
+                None => {
+                    self.state[j] = if dir > 0.0 {
+                        ColState::Upper
+                    } else {
+                        ColState::Lower
+                    };
+                    self.x[j] = self.nonbasic_value(j);
+                }
+                Some((k, st)) => {
+                    let b = self.basis[k];
+                    self.state[b] = st;
+                    self.x[b] = self.nonbasic_value(b);
+                    self.state[j] = ColState::Basic;
+                    self.basis[k] = j;
+                    self.pivot(k, &alpha);
+                }
+            }
+            degenerate = if step <= PRIMAL_TOL {
+                degenerate + 1
+            } else {
+                0
+            };
+        }
+    }
+
+    /// dual simplex, returns None without doing anything if the basis is not dual feasible
+    fn dual(&mut self) -> Option<LpStatus> {
+        let y = self.duals(&self.cost);
+        let dual_feasible = (0..self.cols.len()).all(|j| {
+            let d = self.reduced_cost(j, &self.cost, &y);
+            match self.state[j] {
+                _ if self.lb[j] == self.ub[j] => true,
+                ColState::Basic => true,
+                ColState::Lower => d >= -DUAL_TOL,
+                ColState::Upper => d <= DUAL_TOL,
+                ColState::Zero => d.abs() <= DUAL_TOL,
+            }
+        });
+        if !dual_feasible {
+            return None;
+        }
+        let mut degenerate = 0;
+        loop {
+            let bland = degenerate >= DEGENERATE_LIMIT;
+            // (basis position, direction the leaving variable has to move, bound violation)
+            let mut leave: Option<(usize, f64, f64)> = None;
+            for (k, &b) in self.basis.iter().enumerate() {
+                let (violation, dir) = if self.x[b] < self.lb[b] - PRIMAL_TOL {
+                    (self.lb[b] - self.x[b], 1.0)
+                } else if self.x[b] > self.ub[b] + PRIMAL_TOL {
+                    (self.x[b] - self.ub[b], -1.0)
+                } else {
+                    continue;
+                };
+                if leave.is_none_or(|(_, _, best)| violation > best) {
+                    leave = Some((k, dir, violation));
+                    if bland {
+                        break;
+                    }
+                }
+            }
+            let Some((r, dir, _)) = leave else {
+                return Some(LpStatus::Optimal);
+            };
+            self.iterations += 1;
+
+            let y = self.duals(&self.cost);
+            let rho = &self.binv[r];
+            // (column, ratio, |alpha_rj|)
+            let mut enter: Option<(usize, f64, f64)> = None;
+            for j in 0..self.cols.len() {
+                if self.state[j] == ColState::Basic || self.lb[j] == self.ub[j] {
+                    continue;
+                }
+                let a: f64 = self.cols[j].iter().map(|&(i, v)| rho[i] * v).sum();
+                let eligible = match self.state[j] {
+                    ColState::Lower => dir * a < -PIVOT_TOL,
+                    ColState::Upper => dir * a > PIVOT_TOL,
+                    _ => a.abs() > PIVOT_TOL,
+                };
+                if !eligible {
+                    continue;
+                }
+                let ratio = self.reduced_cost(j, &self.cost, &y).abs() / a.abs();
+                let better = match enter {
+                    None => true,
+                    Some((_, best, _)) if ratio < best => true,
+                    Some((_, best, pa)) => !bland && ratio == best && a.abs() > pa,
+                };
+                if better {
+                    enter = Some((j, ratio, a.abs()));
+                }
+            }
+            let Some((q, ratio, _)) = enter else {
+                return Some(LpStatus::Infeasible);
+            };
+
+            let alpha = self.ftran(q);
+            let b = self.basis[r];
+            let target = if dir > 0.0 { self.lb[b] } else { self.ub[b] };
+            let t = (self.x[b] - target) / alpha[r];
+            self.x[q] += t;
+            for (k, &a) in alpha.iter().enumerate() {
+                self.x[self.basis[k]] -= t * a;
+            }
+            self.state[b] = if dir > 0.0 {
+                ColState::Lower
+            } else {
+                ColState::Upper
+            };
+            self.x[b] = target;
+            self.state[q] = ColState::Basic;
+            self.basis[r] = q;
+            self.pivot(r, &alpha);
+            degenerate = if ratio <= DUAL_TOL { degenerate + 1 } else { 0 };
+        }
+    }
+}
+
+/// optimizes the objective of `mb` subject to its constraints and bounds, ignoring integrality
+pub fn solve(mb: &ModelBuilder) -> LpSolution {
+    Simplex::new(mb).solve()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn optimal() {
+        // max 3x + 5y st x <= 4, 2y <= 12, 3x + 2y <= 18
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, f64::INFINITY, false, "x".to_string());
+        let y = mb.add_var(0.0, f64::INFINITY, false, "y".to_string());
+        mb.add_constraint(Expression::from(x) << 4);
+        mb.add_constraint((2 * y) << 12);
+        mb.add_constraint((3 * x + 2 * y) << 18);
+        mb.maximize(3 * x + 5 * y);
+        let sol = solve(&mb);
+        assert_eq!(sol.status, LpStatus::Optimal);
+        assert_close(sol.obj, 36.0);
+        assert_close(sol.value(x), 2.0);
+        assert_close(sol.value(y), 6.0);
+    }
+
+    #[test]
+    fn bounds_and_phase1() {
+        // min x + y st x + y >= 3, x - y <= 1, -1 <= x <= 10, y free
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(-1.0, 10.0, false, "x".to_string());
+        let y = mb.add_var(f64::NEG_INFINITY, f64::INFINITY, false, "y".to_string());
+        mb.add_constraint((x + y) >> 3);
+        mb.add_constraint((x - y) << 1);
+        mb.add_constraint((y - 2 * x) << 0);
+        mb.minimize(x + y);
+        let sol = solve(&mb);
+        assert_eq!(sol.status, LpStatus::Optimal);
+        assert_close(sol.obj, 3.0);
+        assert!(sol.value(x) + sol.value(y) >= 3.0 - 1e-6);
+        assert!(sol.value(x) - sol.value(y) <= 1.0 + 1e-6);
+        assert!(sol.value(y) - 2.0 * sol.value(x) <= 1e-6);
+    }
+
+    #[test]
+    fn infeasible() {
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, 5.0, false, "x".to_string());
+        let y = mb.add_var(0.0, 5.0, false, "y".to_string());
+        mb.add_constraint((x + y) >> 4);
+        mb.add_constraint((x - y) >> 3);
+        mb.add_constraint(Expression::from(x) << 3);
+        mb.minimize(x + y);
+        let sol = solve(&mb);
+        assert_eq!(sol.status, LpStatus::Infeasible);
+    }
+
+    #[test]
+    fn warm_start() {
+        let mut mb = ModelBuilder::new();
+        let x: Vec<VariableId> = (0..6)
+            .map(|i| mb.add_var(0.0, 4.0, true, format!("x{}", i)))
+            .collect();
+        let weight = x
+            .iter()
+            .enumerate()
+            .fold(Expression::cons(0.0), |acc, (i, &v)| {
+                acc + (i as i64 + 2) * v
+            });
+        let value = x
+            .iter()
+            .enumerate()
+            .fold(Expression::cons(0.0), |acc, (i, &v)| {
+                acc + (7 - i as i64) * v
+            });
+        mb.add_constraint(weight << 23);
+        mb.maximize(value);
+        let mut simplex = Simplex::new(&mb);
+        assert_eq!(simplex.solve().status, LpStatus::Optimal);
+
+        mb.set_bounds(x[0], 0.0, 1.5);
+        simplex.set_bounds(x[0], 0.0, 1.5);
+        let cut = (x[1] + x[2]) << 3;
+        mb.add_constraint(cut.clone());
+        simplex.add_constraint(cut);
+        let basis = simplex.basis();
+        let warm = simplex.solve();
+        let cold = solve(&mb);
+        assert_eq!(warm.status, LpStatus::Optimal);
+        assert_close(warm.obj, cold.obj);
+        assert!(warm.iterations <= cold.iterations);
+
+        let mut restored = Simplex::new(&mb);
+        restored.set_basis(&basis);
+        assert_close(restored.solve().obj, cold.obj);
+
+        simplex.set_bounds(x[3], 5.0, 6.0);
+        assert_eq!(simplex.solve().status, LpStatus::Infeasible);
+    }
+
+    #[test]
+    fn unbounded() {
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, f64::INFINITY, false, "x".to_string());
+        let y = mb.add_var(0.0, f64::INFINITY, false, "y".to_string());
+        mb.add_constraint((x - y) << 1);
+        mb.maximize(x + y);
+        let sol = solve(&mb);
+        assert_eq!(sol.status, LpStatus::Unbounded);
+        assert_eq!(sol.obj, f64::INFINITY);
+    }
+
+    #[test]
+    fn equality_and_range() {
+        // max x + 2y + z st x + y + z = 4, 1 <= x - y <= 2, 0 <= x, y, z <= 3
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, 3.0, false, "x".to_string());
+        let y = mb.add_var(0.0, 3.0, false, "y".to_string());
+        let z = mb.add_var(0.0, 3.0, false, "z".to_string());
+        mb.add_constraint((x + y + z).eq(4));
+        mb.add_constraint((x - y).between(1.0, 2.0));
+        mb.maximize(x + 2 * y + z);
+        let sol = solve(&mb);
+        assert_eq!(sol.status, LpStatus::Optimal);
+        assert_close(sol.obj, 5.5);
+        assert_close(sol.value(x), 2.5);
+        assert_close(sol.value(y), 1.5);
+        assert_close(sol.value(z), 0.0);
+    }
+}
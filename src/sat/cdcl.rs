@@ -0,0 +1,909 @@
+use crate::core::*;
+use crate::sat::cnf::*;
+
+/// multiplier applied to the variable activity increment at each conflict is 1 / VAR_DECAY
+const VAR_DECAY: f64 = 0.95;
+/// multiplier applied to the clause activity increment at each conflict is 1 / CLAUSE_DECAY
+const CLAUSE_DECAY: f64 = 0.999;
+/// number of conflicts of the first restart, multiplied by the luby sequence
+const RESTART_UNIT: f64 = 100.0;
+/// initial maximum number of learnt clauses before reduction, multiplied by LEARNT_GROWTH each reduction
+const MIN_LEARNTS: f64 = 2000.0;
+const LEARNT_GROWTH: f64 = 1.1;
+/// learnt clauses with a literal block distance at most this are never deleted
+const GLUE_LBD: u32 = 2;
+const NO_REASON: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SatResult {
+    Sat,
+    Unsat,
+    /// stopped before finding the answer
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SatStats {
+    pub conflicts: u64,
+    pub decisions: u64,
+    pub propagations: u64,
+    pub restarts: u64,
+}
+
+#[derive(Clone)]
+struct Clause {
+    /// the first two literals are watched, for a reason clause the first one is the implied literal
+    lits: Vec<Lit>,
+    learnt: bool,
+    deleted: bool,
+    activity: f64,
+    /// number of distinct decision levels when learnt
+    lbd: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Watcher {
+    cref: u32,
+    /// a literal of the clause, if it is true the clause does not need to be visited
+    blocker: Lit,
+}
+
+/// max heap of variables ordered by activity
+#[derive(Clone, Default)]
+struct VarHeap {
+    heap: Vec<usize>,
+    /// position of each variable in heap, usize::MAX if absent
+    pos: Vec<usize>,
+}
+impl VarHeap {
+    fn contains(&self, v: usize) -> bool {
+        self.pos[v] != usize::MAX
+    }
+    fn push(&mut self, v: usize, act: &[f64]) {
+        if v >= self.pos.len() {
+            self.pos.resize(v + 1, usize::MAX);
+        }
+        if !self.contains(v) {
+            self.pos[v] = self.heap.len();
+            self.heap.push(v);
+            self.sift_up(self.heap.len() - 1, act);
+        }
+    }
+    fn pop(&mut self, act: &[f64]) -> Option<usize> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+        self.pos[top] = usize::MAX;
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.pos[last] = 0;
+            self.sift_down(0, act);
+        }
+        Some(top)
+    }
+    /// restores the order after the activity of v increased
+    fn increased(&mut self, v: usize, act: &[f64]) {
+        if self.contains(v) {
+            self.sift_up(self.pos[v], act);
+        }
+    }
+    fn sift_up(&mut self, mut i: usize, act: &[f64]) {
+        let v = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if act[self.heap[parent]] >= act[v] {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.pos[self.heap[i]] = i;
+            i = parent;
+        }
+        self.heap[i] = v;
+        self.pos[v] = i;
+    }
+    fn sift_down(&mut self, mut i: usize, act: &[f64]) {
+        let v = self.heap[i];
+        loop {
+            let mut child = 2 * i + 1;
+            if child >= self.heap.len() {
+                break;
+            }
+            if child + 1 < self.heap.len() && act[self.heap[child + 1]] > act[self.heap[child]] {
+                child += 1;
+            }
+            if act[self.heap[child]] <= act[v] {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.pos[self.heap[i]] = i;
+            i = child;
+        }
+        self.heap[i] = v;
+        self.pos[v] = i;
+    }
+}
+
+/// i-th element of the luby sequence 1 1 2 1 1 2 4 1 1 2 ...
+fn luby(mut i: u64) -> f64 {
+    let (mut size, mut seq) = (1u64, 0i32);
+    while size < i + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != i {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        i %= size;
+    }
+    2f64.powi(seq)
+}
+
+/// conflict driven clause learning SAT solver with two watched literals, first UIP learning,
+/// EVSIDS branching with phase saving, luby restarts and deletion of learnt clauses by LBD
+#[derive(Clone)]
+pub struct Cdcl {
+    clauses: Vec<Clause>,
+    num_deleted: usize,
+    num_learnts: usize,
+    max_learnts: f64,
+    /// watchers of the clauses watching each literal, indexed by literal code
+    watches: Vec<Vec<Watcher>>,
+    /// 1 if true, -1 if false, 0 if unassigned
+    value: Vec<i8>,
+    level: Vec<u32>,
+    reason: Vec<u32>,
+    /// saved phase of each variable
+    polarity: Vec<bool>,
+    activity: Vec<f64>,
+    var_inc: f64,
+    cla_inc: f64,
+    heap: VarHeap,
+    trail: Vec<Lit>,
+    /// start of each decision level in the trail
+    trail_lim: Vec<usize>,
+    /// next literal of the trail to propagate
+    qhead: usize,
+    seen: Vec<bool>,
+    /// literals forced to true by the current call, decided first
+    assumptions: Vec<Lit>,
+    core: Vec<Lit>,
+    /// false once the clauses are unsatisfiable at level 0
+    ok: bool,
+    model: Vec<bool>,
+    stats: SatStats,
+    /// DRAT proof in text format, if enabled
+    proof: Option<String>,
+}
+impl Default for Cdcl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ClauseDb for Cdcl {
+    fn new_var(&mut self) -> usize {
+        Cdcl::new_var(self)
+    }
+    fn add_clause(&mut self, clause: &[Lit]) {
+        Cdcl::add_clause(self, clause);
+    }
+}
+impl From<&Cnf> for Cdcl {
+    fn from(cnf: &Cnf) -> Self {
+        let mut s = Self::new();
+        while s.num_vars() < cnf.num_vars {
+            s.new_var();
+        }
+        for c in cnf.clauses.iter() {
+            s.add_clause(c);
+        }
+        s
+    }
+}
+impl Cdcl {
+    pub fn new() -> Self {
+        Self {
+            clauses: vec![],
+            num_deleted: 0,
+            num_learnts: 0,
+            max_learnts: MIN_LEARNTS,
+            watches: vec![],
+            value: vec![],
+            level: vec![],
+            reason: vec![],
+            polarity: vec![],
+            activity: vec![],
+            var_inc: 1.0,
+            cla_inc: 1.0,
+            heap: VarHeap::default(),
+            trail: vec![],
+            trail_lim: vec![],
+            qhead: 0,
+            seen: vec![],
+            assumptions: vec![],
+            core: vec![],
+            ok: true,
+            model: vec![],
+            stats: SatStats::default(),
+            proof: None,
+        }
+    }
+    pub fn num_vars(&self) -> usize {
+        self.value.len()
+    }
+    pub fn new_var(&mut self) -> usize {
+        let v = self.value.len();
+        self.value.push(0);
+        self.level.push(0);
+        self.reason.push(NO_REASON);
+        self.polarity.push(false);
+        self.activity.push(0.0);
+        self.seen.push(false);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        self.heap.push(v, &self.activity);
+        v
+    }
+    pub fn stats(&self) -> SatStats {
+        self.stats
+    }
+    /// starts recording a DRAT proof of the clauses learnt and deleted,
+    /// should be called before adding the clauses to be checked against the input formula
+    pub fn enable_proof(&mut self) {
+        self.proof.get_or_insert_with(String::new);
+    }
+    /// DRAT proof in text format recorded since enable_proof, ends with the empty clause once unsatisfiable
+    pub fn proof(&self) -> Option<&str> {
+        self.proof.as_deref()
+    }
+    /// assumptions of the last call that are enough for the clauses to be unsatisfiable,
+    /// empty if the last call was not unsatisfiable or if the clauses alone are
+    pub fn unsat_core(&self) -> &[Lit] {
+        &self.core
+    }
+    /// value of each variable in the last satisfying assignment found, empty if none
+    pub fn model(&self) -> &[bool] {
+        &self.model
+    }
+    /// adds a clause, creating the variables it uses if needed,
+    /// returns false if the clauses are now trivially unsatisfiable
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        debug_assert!(self.trail_lim.is_empty());
+        if !self.ok {
+            return false;
+        }
+        let mut c = lits.to_vec();
+        c.sort();
+        c.dedup();
+        if let Some(max) = c.iter().map(|l| l.var()).max() {
+            while self.num_vars() <= max {
+                self.new_var();
+            }
+        }
+        if c.windows(2).any(|w| w[0] == !w[1]) || c.iter().any(|&l| self.lit_value(l) == 1) {
+            return true;
+        }
+        let len = c.len();
+        c.retain(|&l| self.lit_value(l) == 0);
+        if c.len() < len {
+            self.log(false, &c);
+        }
+        match c.len() {
+            0 => self.ok = false,
+            1 => {
+                self.enqueue(c[0], NO_REASON);
+                self.ok = self.propagate().is_none();
+                if !self.ok {
+                    self.log(false, &[]);
+                }
+            }
+            _ => {
+                self.attach(c, false, 0);
+            }
+        }
+        self.ok
+    }
+    /// solves the current clauses
+    pub fn solve(&mut self) -> SatResult {
+        self.solve_with(|| false)
+    }
+    /// solves the current clauses, stop is called after each conflict and
+    /// the search is interrupted with SatResult::Unknown if it returns true
+    pub fn solve_with<F: FnMut() -> bool>(&mut self, stop: F) -> SatResult {
+        self.solve_assuming_with(&[], stop)
+    }
+    /// solves the current clauses with the assumption literals forced to true,
+    /// clauses can still be added after the call and learnt clauses are kept
+    pub fn solve_assuming(&mut self, assumptions: &[Lit]) -> SatResult {
+        self.solve_assuming_with(assumptions, || false)
+    }
+    /// solve_assuming interrupted with SatResult::Unknown once stop returns true, see solve_with
+    pub fn solve_assuming_with<F: FnMut() -> bool>(
+        &mut self,
+        assumptions: &[Lit],
+        mut stop: F,
+    ) -> SatResult {
+        self.model.clear();
+        self.core.clear();
+        if !self.ok {
+            return SatResult::Unsat;
+        }
+        if let Some(max) = assumptions.iter().map(|l| l.var()).max() {
+            while self.num_vars() <= max {
+                self.new_var();
+            }
+        }
+        self.assumptions = assumptions.to_vec();
+        let mut restart = 0;
+        loop {
+            let budget = (luby(restart) * RESTART_UNIT) as u64;
+            if let Some(res) = self.search(budget, &mut stop) {
+                if res == SatResult::Sat {
+                    self.model = self.value.iter().map(|&v| v == 1).collect();
+                }
+                self.cancel_until(0);
+                return res;
+            }
+            restart += 1;
+            self.stats.restarts += 1;
+        }
+    }
+
+    /// adds a clause addition or deletion to the proof
+    fn log(&mut self, deletion: bool, lits: &[Lit]) {
+        use std::fmt::Write;
+        if let Some(proof) = self.proof.as_mut() {
+            if deletion {
+                proof.push_str("d ");
+            }
+            for l in lits {
+                write!(proof, "{} ", l.to_dimacs()).unwrap();
+            }
+            proof.push_str("0\n");
+        }
+    }
+    fn lit_value(&self, l: Lit) -> i8 {
+        lit_value(&self.value, l)
+    }
+    fn decision_level(&self) -> u32 {
+        self.trail_lim.len() as u32
+    }
+    fn enqueue(&mut self, l: Lit, reason: u32) {
+        let v = l.var();
+        self.value[v] = if l.is_pos() { 1 } else { -1 };
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(l);
+    }
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool, lbd: u32) -> u32 {
+        let cref = self.clauses.len() as u32;
+        self.watches[lits[0].code()].push(Watcher {
+            cref,
+            blocker: lits[1],
+        });
+        self.watches[lits[1].code()].push(Watcher {
+            cref,
+            blocker: lits[0],
+        });
+        self.clauses.push(Clause {
+            lits,
+            learnt,
+            deleted: false,
+            activity: 0.0,
+            lbd,
+        });
+        if learnt {
+            self.num_learnts += 1;
+        }
+        cref
+    }
+    /// propagates the trail, returns a conflicting clause if any
+    fn propagate(&mut self) -> Option<u32> {
+        while self.qhead < self.trail.len() {
+            let false_lit = !self.trail[self.qhead];
+            self.qhead += 1;
+            self.stats.propagations += 1;
+            let mut ws = std::mem::take(&mut self.watches[false_lit.code()]);
+            let (mut i, mut j) = (0, 0);
+            let mut conflict = None;
+            while i < ws.len() {
+                let w = ws[i];
+                i += 1;
+                if lit_value(&self.value, w.blocker) == 1 {
+                    ws[j] = w;
+                    j += 1;
+                    continue;
+                }
+                let c = &mut self.clauses[w.cref as usize];
+                if c.deleted {
+                    continue;
+                }
+                if c.lits[0] == false_lit {
+                    c.lits.swap(0, 1);
+                }
+                let first = c.lits[0];
+                let nw = Watcher {
+                    cref: w.cref,
+                    blocker: first,
+                };
+                if first != w.blocker && lit_value(&self.value, first) == 1 {
+                    ws[j] = nw;
+                    j += 1;
+                    continue;
+                }
+                if let Some(k) =
+                    (2..c.lits.len()).find(|&k| lit_value(&self.value, c.lits[k]) != -1)
+                {
+                    c.lits.swap(1, k);
+                    self.watches[c.lits[1].code()].push(nw);
+                    continue;
+                }
+                ws[j] = nw;
+                j += 1;
+                if lit_value(&self.value, first) == -1 {
+                    conflict = Some(w.cref);
+                    while i < ws.len() {
+                        ws[j] = ws[i];
+                        i += 1;
+                        j += 1;
+                    }
+                } else {
+                    self.enqueue(first, w.cref);
+                }
+            }
+            ws.truncate(j);
+            self.watches[false_lit.code()] = ws;
+            if conflict.is_some() {
+                self.qhead = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+    fn bump_var(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() {
+                *a *= 1e-100;
+            }
+            self.var_inc *= 1e-100;
+        }
+        self.heap.increased(v, &self.activity);
+    }
+    fn bump_clause(&mut self, cref: u32) {
+        let c = &mut self.clauses[cref as usize];
+        c.activity += self.cla_inc;
+        if c.activity > 1e20 {
+            for c in self.clauses.iter_mut().filter(|c| c.learnt) {
+                c.activity *= 1e-20;
+            }
+            self.cla_inc *= 1e-20;
+        }
+    }
+    /// first UIP learnt clause with the asserting literal first and a literal
+    /// of the backtrack level second, returns it with the backtrack level and its LBD
+    fn analyze(&mut self, mut confl: u32) -> (Vec<Lit>, u32, u32) {
+        let dl = self.decision_level();
+        let mut learnt = vec![Lit::pos(0)];
+        let mut path = 0;
+        let mut p: Option<Lit> = None;
+        let mut idx = self.trail.len();
+        loop {
+            if self.clauses[confl as usize].learnt {
+                self.bump_clause(confl);
+            }
+            let start = if p.is_none() { 0 } else { 1 };
+            for k in start..self.clauses[confl as usize].lits.len() {
+                let q = self.clauses[confl as usize].lits[k];
+                let v = q.var();
+                if !self.seen[v] && self.level[v] > 0 {
+                    self.seen[v] = true;
+                    self.bump_var(v);
+                    if self.level[v] >= dl {
+                        path += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+            loop {
+                idx -= 1;
+                if self.seen[self.trail[idx].var()] {
+                    break;
+                }
+            }
+            let l = self.trail[idx];
+            p = Some(l);
+            confl = self.reason[l.var()];
+            self.seen[l.var()] = false;
+            path -= 1;
+            if path == 0 {
+                break;
+            }
+        }
+        learnt[0] = !p.unwrap();
+
+        // removes literals implied by the other ones through their reason
+        let to_clear = learnt.clone();
+        let mut k = 1;
+        while k < learnt.len() {
+            let r = self.reason[learnt[k].var()];
+            let redundant = r != NO_REASON
+                && self.clauses[r as usize].lits[1..]
+                    .iter()
+                    .all(|q| self.seen[q.var()] || self.level[q.var()] == 0);
+            if redundant {
+                learnt.swap_remove(k);
+            } else {
+                k += 1;
+            }
+        }
+        for l in to_clear {
+            self.seen[l.var()] = false;
+        }
+
+        let mut bt = 0;
+        if learnt.len() > 1 {
+            let max = (1..learnt.len())
+                .max_by_key(|&k| self.level[learnt[k].var()])
+                .unwrap();
+            learnt.swap(1, max);
+            bt = self.level[learnt[1].var()];
+        }
+        let mut levels: Vec<u32> = learnt.iter().map(|l| self.level[l.var()]).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        (learnt, bt, levels.len() as u32)
+    }
+    /// sets the core to the assumptions implying the negation of the assumption p
+    fn analyze_final(&mut self, p: Lit) {
+        self.core = vec![p];
+        if self.level[p.var()] == 0 {
+            return;
+        }
+        self.seen[p.var()] = true;
+        for k in (self.trail_lim[0]..self.trail.len()).rev() {
+            let x = self.trail[k];
+            if !self.seen[x.var()] {
+                continue;
+            }
+            let r = self.reason[x.var()];
+            if r == NO_REASON {
+                // decisions below the assumption levels are assumptions
+                self.core.push(x);
+            } else {
+                for &q in self.clauses[r as usize].lits[1..].iter() {
+                    if self.level[q.var()] > 0 {
+                        self.seen[q.var()] = true;
+                    }
+                }
+            }
+            self.seen[x.var()] = false;
+        }
+    }
+    fn cancel_until(&mut self, level: u32) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let start = self.trail_lim[level as usize];
+        for k in (start..self.trail.len()).rev() {
+            let l = self.trail[k];
+            let v = l.var();
+            self.value[v] = 0;
+            self.reason[v] = NO_REASON;
+            self.polarity[v] = l.is_pos();
+            self.heap.push(v, &self.activity);
+        }
+        self.trail.truncate(start);
+        self.trail_lim.truncate(level as usize);
+        self.qhead = self.trail.len();
+    }
+    fn locked(&self, cref: u32) -> bool {
+        let l = self.clauses[cref as usize].lits[0];
+        self.lit_value(l) == 1 && self.reason[l.var()] == cref
+    }
+    /// deletes the less useful half of the learnt clauses
+    fn reduce_db(&mut self) {
+        let mut learnts: Vec<u32> = (0..self.clauses.len() as u32)
+            .filter(|&c| {
+                let cl = &self.clauses[c as usize];
+                cl.learnt && !cl.deleted && cl.lbd > GLUE_LBD && !self.locked(c)
+            })
+            .collect();
+        learnts.sort_by(|&a, &b| {
+            let (a, b) = (&self.clauses[a as usize], &self.clauses[b as usize]);
+            b.lbd.cmp(&a.lbd).then(a.activity.total_cmp(&b.activity))
+        });
+        for &c in learnts[..learnts.len() / 2].iter() {
+            let lits = std::mem::take(&mut self.clauses[c as usize].lits);
+            self.log(true, &lits);
+            self.clauses[c as usize].deleted = true;
+            self.num_deleted += 1;
+            self.num_learnts -= 1;
+        }
+        self.max_learnts *= LEARNT_GROWTH;
+        if self.num_deleted * 2 > self.clauses.len() {
+            self.collect_garbage();
+        }
+    }
+    /// removes deleted clauses from memory, renumbering the other ones
+    fn collect_garbage(&mut self) {
+        let mut new_ref = vec![NO_REASON; self.clauses.len()];
+        let mut clauses = Vec::with_capacity(self.clauses.len() - self.num_deleted);
+        for (i, c) in std::mem::take(&mut self.clauses).into_iter().enumerate() {
+            if !c.deleted {
+                new_ref[i] = clauses.len() as u32;
+                clauses.push(c);
+            }
+        }
+        self.clauses = clauses;
+        self.num_deleted = 0;
+        for ws in self.watches.iter_mut() {
+            ws.retain_mut(|w| {
+                w.cref = new_ref[w.cref as usize];
+                w.cref != NO_REASON
+            });
+        }
+        for l in self.trail.iter() {
+            let r = &mut self.reason[l.var()];
+            if *r != NO_REASON {
+                *r = new_ref[*r as usize];
+            }
+        }
+    }
+    /// runs until a result, or returns None after the given number of conflicts to restart
+    fn search<F: FnMut() -> bool>(&mut self, budget: u64, stop: &mut F) -> Option<SatResult> {
+        let mut conflicts = 0;
+        loop {
+            if let Some(confl) = self.propagate() {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    self.log(false, &[]);
+                    return Some(SatResult::Unsat);
+                }
+                let (learnt, bt, lbd) = self.analyze(confl);
+                self.log(false, &learnt);
+                self.cancel_until(bt);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], NO_REASON);
+                } else {
+                    let l = learnt[0];
+                    let cref = self.attach(learnt, true, lbd);
+                    self.bump_clause(cref);
+                    self.enqueue(l, cref);
+                }
+                self.var_inc /= VAR_DECAY;
+                self.cla_inc /= CLAUSE_DECAY;
+                if stop() {
+                    return Some(SatResult::Unknown);
+                }
+            } else {
+                if conflicts >= budget {
+                    self.cancel_until(0);
+                    return None;
+                }
+                if self.num_learnts as f64 >= self.max_learnts + self.trail.len() as f64 {
+                    self.reduce_db();
+                }
+                let mut next = None;
+                while (self.decision_level() as usize) < self.assumptions.len() {
+                    let p = self.assumptions[self.decision_level() as usize];
+                    match self.lit_value(p) {
+                        // an empty decision level keeps levels and assumptions aligned
+                        1 => self.trail_lim.push(self.trail.len()),
+                        -1 => {
+                            self.analyze_final(p);
+                            return Some(SatResult::Unsat);
+                        }
+                        _ => {
+                            next = Some(p);
+                            break;
+                        }
+                    }
+                }
+                if next.is_none() {
+                    let v = loop {
+                        match self.heap.pop(&self.activity) {
+                            Some(v) if self.value[v] != 0 => {}
+                            v => break v,
+                        }
+                    };
+                    let Some(v) = v else {
+                        return Some(SatResult::Sat);
+                    };
+                    next = Some(Lit::new(v, self.polarity[v]));
+                }
+                self.stats.decisions += 1;
+                self.trail_lim.push(self.trail.len());
+                self.enqueue(next.unwrap(), NO_REASON);
+            }
+        }
+    }
+}
+
+fn lit_value(value: &[i8], l: Lit) -> i8 {
+    if l.is_pos() {
+        value[l.var()]
+    } else {
+        -value[l.var()]
+    }
+}
+
+/// runs a new Cdcl on the problem, a satisfying assignment is reported with objective 0
+/// and unsatisfiability with a dual bound of 1, sk.iter is called after each conflict
+#[derive(Clone, Default)]
+pub struct CdclSolver;
+impl Solver<Cnf> for CdclSolver {
+    fn solve<SK: SolutionKeeper<Cnf>, S: StopCondition<SatObj>>(
+        &mut self,
+        p: Cnf,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let mut cdcl = Cdcl::from(&p);
+        let res = cdcl.solve_with(|| {
+            sk.iter();
+            stop.stop(sk.best_obj(), SatObj(0))
+        });
+        match res {
+            SatResult::Sat => {
+                sk.add_solution(&cdcl.model().to_vec(), SatObj(0));
+                sk.add_dual_bound(SatObj(0));
+            }
+            SatResult::Unsat => sk.add_dual_bound(SatObj(1)),
+            SatResult::Unknown => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_cnf(rng: &mut Splitmix64, n: usize, m: usize) -> Cnf {
+        let mut cnf = Cnf::new();
+        cnf.num_vars = n;
+        for _ in 0..m {
+            let c: Vec<Lit> = (0..3)
+                .map(|_| {
+                    Lit::new(
+                        rng.next_u64() as usize % n,
+                        rng.next_u64().is_multiple_of(2),
+                    )
+                })
+                .collect();
+            cnf.add_clause(&c);
+        }
+        cnf
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Splitmix64::from_u64(5);
+        for _ in 0..200 {
+            let n = 10;
+            let cnf = random_cnf(&mut rng, n, 43);
+            let sat = (0..1u32 << n).any(|code| {
+                let values: Vec<bool> = (0..n).map(|i| code >> i & 1 == 1).collect();
+                cnf.num_violated(&values) == 0
+            });
+            let mut cdcl = Cdcl::from(&cnf);
+            let res = cdcl.solve();
+            assert_eq!(
+                res,
+                if sat {
+                    SatResult::Sat
+                } else {
+                    SatResult::Unsat
+                }
+            );
+            if sat {
+                assert_eq!(cnf.num_violated(cdcl.model()), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn pigeonhole() {
+        // 8 pigeons in 7 holes
+        let (p, h) = (8, 7);
+        let x = |i: usize, j: usize| i * h + j;
+        let mut cdcl = Cdcl::new();
+        for i in 0..p {
+            cdcl.add_clause(&(0..h).map(|j| Lit::pos(x(i, j))).collect::<Vec<_>>());
+        }
+        for j in 0..h {
+            for a in 0..p {
+                for b in a + 1..p {
+                    cdcl.add_clause(&[Lit::neg(x(a, j)), Lit::neg(x(b, j))]);
+                }
+            }
+        }
+        assert_eq!(cdcl.solve(), SatResult::Unsat);
+        assert!(!cdcl.add_clause(&[Lit::pos(0)]));
+    }
+
+    #[test]
+    fn assumptions_and_core() {
+        let mut rng = Splitmix64::from_u64(13);
+        for _ in 0..50 {
+            let n = 10;
+            let mut cnf = random_cnf(&mut rng, n, 20);
+            let mut cdcl = Cdcl::from(&cnf);
+            for _ in 0..10 {
+                let assumptions: Vec<Lit> = (0..4)
+                    .map(|_| {
+                        Lit::new(
+                            rng.next_u64() as usize % n,
+                            rng.next_u64().is_multiple_of(2),
+                        )
+                    })
+                    .collect();
+                let sat_under = |cnf: &Cnf, assumptions: &[Lit]| {
+                    (0..1u32 << n).any(|code| {
+                        let values: Vec<bool> = (0..n).map(|i| code >> i & 1 == 1).collect();
+                        assumptions.iter().all(|l| l.eval(&values))
+                            && cnf.num_violated(&values) == 0
+                    })
+                };
+                let res = cdcl.solve_assuming(&assumptions);
+                if sat_under(&cnf, &assumptions) {
+                    assert_eq!(res, SatResult::Sat);
+                    assert_eq!(cnf.num_violated(cdcl.model()), 0);
+                    assert!(assumptions.iter().all(|l| l.eval(cdcl.model())));
+                } else {
+                    assert_eq!(res, SatResult::Unsat);
+                    let core = cdcl.unsat_core().to_vec();
+                    assert!(core.iter().all(|l| assumptions.contains(l)));
+                    assert!(!sat_under(&cnf, &core));
+                }
+                let c: Vec<Lit> = (0..3)
+                    .map(|_| {
+                        Lit::new(
+                            rng.next_u64() as usize % n,
+                            rng.next_u64().is_multiple_of(2),
+                        )
+                    })
+                    .collect();
+                cnf.add_clause(&c);
+                cdcl.add_clause(&c);
+            }
+        }
+
+        // x0 -> x1 -> ... -> x4
+        let mut cdcl = Cdcl::new();
+        for i in 0..4 {
+            cdcl.add_clause(&[Lit::neg(i), Lit::pos(i + 1)]);
+        }
+        let assumptions = [Lit::pos(7), Lit::pos(0), Lit::neg(5), Lit::neg(4)];
+        assert_eq!(cdcl.solve_assuming(&assumptions), SatResult::Unsat);
+        let mut core = cdcl.unsat_core().to_vec();
+        core.sort();
+        assert_eq!(core, vec![Lit::pos(0), Lit::neg(4)]);
+        assert_eq!(cdcl.solve(), SatResult::Sat);
+        assert!(cdcl.unsat_core().is_empty());
+    }
+
+    #[test]
+    fn solver_reports() {
+        let mut rng = Splitmix64::from_u64(9);
+        let cnf = random_cnf(&mut rng, 150, 600);
+        let mut sk = SimpleSolutionKeeper::<Cnf>::default();
+        let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+        CdclSolver.solve(cnf.clone(), &mut sk, stop);
+        assert_eq!(sk.best_obj(), SatObj(0));
+        assert_eq!(cnf.obj(&sk.best_solution().unwrap().0), SatObj(0));
+        assert_eq!(sk.dual_bound, SatObj(0));
+
+        let mut cnf = Cnf::new();
+        cnf.add_clause(&[Lit::pos(0), Lit::pos(1)]);
+        cnf.add_clause(&[Lit::neg(0), Lit::pos(1)]);
+        cnf.add_clause(&[Lit::pos(0), Lit::neg(1)]);
+        cnf.add_clause(&[Lit::neg(0), Lit::neg(1)]);
+        let mut sk = SimpleSolutionKeeper::<Cnf>::default();
+        let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+        CdclSolver.solve(cnf, &mut sk, stop);
+        assert!(sk.best_solution().is_none());
+        assert_eq!(sk.dual_bound, SatObj(1));
+    }
+}
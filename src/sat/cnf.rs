@@ -0,0 +1,154 @@
+use std::fmt;
+use std::ops::Not;
+
+use crate::core::*;
+
+/// a boolean variable or its negation, encoded as 2 * variable + negated
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(u32);
+impl Lit {
+    pub fn new(var: usize, positive: bool) -> Self {
+        Self(((var as u32) << 1) | !positive as u32)
+    }
+    pub fn pos(var: usize) -> Self {
+        Self::new(var, true)
+    }
+    pub fn neg(var: usize) -> Self {
+        Self::new(var, false)
+    }
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+    pub fn is_pos(self) -> bool {
+        self.0 & 1 == 0
+    }
+    /// unique index of the literal, 2 * variable + negated
+    pub fn code(self) -> usize {
+        self.0 as usize
+    }
+    /// literal from its DIMACS representation, a non zero signed 1-based variable
+    pub fn from_dimacs(x: i64) -> Self {
+        debug_assert!(x != 0);
+        Self::new(x.unsigned_abs() as usize - 1, x > 0)
+    }
+    pub fn to_dimacs(self) -> i64 {
+        let v = self.var() as i64 + 1;
+        if self.is_pos() {
+            v
+        } else {
+            -v
+        }
+    }
+    /// value of the literal given the value of each variable
+    pub fn eval(self, values: &[bool]) -> bool {
+        values[self.var()] == self.is_pos()
+    }
+}
+impl Not for Lit {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        Self(self.0 ^ 1)
+    }
+}
+impl fmt::Debug for Lit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_pos() {
+            write!(f, "x{}", self.var())
+        } else {
+            write!(f, "¬x{}", self.var())
+        }
+    }
+}
+
+/// clauses and variables storage that encodings can write into
+pub trait ClauseDb {
+    fn new_var(&mut self) -> usize;
+    /// adds the clause, creating the variables it uses if needed
+    fn add_clause(&mut self, clause: &[Lit]);
+}
+
+/// number (or total weight) of violated clauses, unfeas is i64::MAX and unbounded is i64::MIN
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SatObj(pub i64);
+impl From<SatObj> for f64 {
+    fn from(value: SatObj) -> Self {
+        value.0 as f64
+    }
+}
+impl Objective for SatObj {
+    fn unfeas() -> Self {
+        Self(i64::MAX)
+    }
+    fn unbounded() -> Self {
+        Self(i64::MIN)
+    }
+    fn is_feas(&self) -> bool {
+        self.0 != i64::MAX
+    }
+    fn is_bounded(&self) -> bool {
+        self.0 != i64::MIN
+    }
+}
+
+/// boolean formula in conjunctive normal form, a solution is the value of each variable
+/// and its objective the number of violated clauses, 0 iff the formula is satisfied
+#[derive(Clone, Debug, Default)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<Lit>>,
+}
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn new_var(&mut self) -> usize {
+        self.num_vars += 1;
+        self.num_vars - 1
+    }
+    /// adds the clause, creating the variables it uses if needed
+    pub fn add_clause(&mut self, clause: &[Lit]) {
+        if let Some(max) = clause.iter().map(|l| l.var()).max() {
+            self.num_vars = self.num_vars.max(max + 1);
+        }
+        self.clauses.push(clause.to_vec());
+    }
+    pub fn num_violated(&self, values: &[bool]) -> usize {
+        self.clauses
+            .iter()
+            .filter(|c| !c.iter().any(|l| l.eval(values)))
+            .count()
+    }
+}
+impl ClauseDb for Cnf {
+    fn new_var(&mut self) -> usize {
+        Cnf::new_var(self)
+    }
+    fn add_clause(&mut self, clause: &[Lit]) {
+        Cnf::add_clause(self, clause)
+    }
+}
+impl Problem for Cnf {
+    type Sol = Vec<bool>;
+    type Obj = SatObj;
+    fn obj(&self, sol: &Self::Sol) -> Self::Obj {
+        if self.is_feasible(sol) {
+            SatObj(self.num_violated(sol) as i64)
+        } else {
+            SatObj::unfeas()
+        }
+    }
+    fn is_feasible(&self, sol: &Self::Sol) -> bool {
+        sol.len() == self.num_vars
+    }
+}
+impl Reduction<Cnf> for Cnf {
+    fn reduce_from(p: &Cnf) -> Self {
+        p.clone()
+    }
+    fn lift_solution_to(&self, sol: Self::Sol) -> Vec<bool> {
+        sol
+    }
+    fn lift_obj_to(&self, obj: Self::Obj) -> SatObj {
+        obj
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::sat::cdcl::SatResult;
+use crate::sat::cnf::*;
+
+/// error while reading a DIMACS file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DimacsError {
+    /// 1-based line of the error
+    pub line: usize,
+    pub msg: String,
+}
+impl fmt::Display for DimacsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+impl std::error::Error for DimacsError {}
+fn err<T>(line: usize, msg: impl Into<String>) -> Result<T, DimacsError> {
+    Err(DimacsError {
+        line,
+        msg: msg.into(),
+    })
+}
+
+/// reads a formula in the DIMACS CNF format, clauses can span several lines
+/// and everything after a line starting with % is ignored
+pub fn read_dimacs(s: &str) -> Result<Cnf, DimacsError> {
+    let mut cnf = Cnf::new();
+    let mut header: Option<(usize, usize)> = None;
+    let mut clause = vec![];
+    let mut last_line = 0;
+    for (l, line) in s.lines().enumerate() {
+        let line_no = l + 1;
+        last_line = line_no;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+        if line.starts_with('%') {
+            break;
+        }
+        if line.starts_with('p') {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if header.is_some() {
+                return err(line_no, "repeated header");
+            }
+            match fields[..] {
+                ["p", "cnf", v, c] => match (v.parse(), c.parse()) {
+                    (Ok(v), Ok(c)) => header = Some((v, c)),
+                    _ => return err(line_no, "invalid header"),
+                },
+                _ => return err(line_no, "expected p cnf <variables> <clauses>"),
+            }
+            continue;
+        }
+        let Some((num_vars, _)) = header else {
+            return err(line_no, "clause before the header");
+        };
+        for tok in line.split_whitespace() {
+            let x: i64 = match tok.parse() {
+                Ok(x) => x,
+                Err(_) => return err(line_no, format!("invalid literal {}", tok)),
+            };
+            if x == 0 {
+                cnf.add_clause(&clause);
+                clause.clear();
+            } else if x.unsigned_abs() as usize > num_vars {
+                return err(line_no, format!("variable {} out of range", x));
+            } else {
+                clause.push(Lit::from_dimacs(x));
+            }
+        }
+    }
+    // the last 0 is often omitted
+    if !clause.is_empty() {
+        cnf.add_clause(&clause);
+    }
+    let Some((num_vars, num_clauses)) = header else {
+        return err(last_line, "missing header");
+    };
+    if cnf.clauses.len() != num_clauses {
+        return err(
+            last_line,
+            format!(
+                "expected {} clauses, found {}",
+                num_clauses,
+                cnf.clauses.len()
+            ),
+        );
+    }
+    cnf.num_vars = num_vars;
+    Ok(cnf)
+}
+
+pub fn write_dimacs<W: Write>(cnf: &Cnf, w: &mut W) -> fmt::Result {
+    writeln!(w, "p cnf {} {}", cnf.num_vars, cnf.clauses.len())?;
+    for c in cnf.clauses.iter() {
+        for l in c.iter() {
+            write!(w, "{} ", l.to_dimacs())?;
+        }
+        writeln!(w, "0")?;
+    }
+    Ok(())
+}
+
+/// writes the result in the SAT competition format, an `s` line
+/// followed by `v` lines with the value of each variable if satisfiable
+pub fn write_solution<W: Write>(res: SatResult, model: &[bool], w: &mut W) -> fmt::Result {
+    match res {
+        SatResult::Sat => writeln!(w, "s SATISFIABLE")?,
+        SatResult::Unsat => return writeln!(w, "s UNSATISFIABLE"),
+        SatResult::Unknown => return writeln!(w, "s UNKNOWN"),
+    }
+    for chunk in model.iter().enumerate().collect::<Vec<_>>().chunks(16) {
+        write!(w, "v")?;
+        for &(v, &value) in chunk {
+            write!(w, " {}", Lit::new(v, value).to_dimacs())?;
+        }
+        writeln!(w)?;
+    }
+    writeln!(w, "v 0")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::cdcl::*;
+
+    /// checks that each added clause follows from the previous ones by unit propagation
+    /// and that the proof derives the empty clause
+    fn check_rup(cnf: &Cnf, proof: &str) {
+        let mut db: Vec<Vec<i64>> = cnf
+            .clauses
+            .iter()
+            .map(|c| c.iter().map(|l| l.to_dimacs()).collect())
+            .collect();
+        let mut empty = false;
+        for line in proof.lines() {
+            let deletion = line.starts_with("d ");
+            let mut c: Vec<i64> = line
+                .trim_start_matches("d ")
+                .split_whitespace()
+                .map(|t| t.parse().unwrap())
+                .filter(|&x| x != 0)
+                .collect();
+            c.sort_unstable();
+            if deletion {
+                let i = db
+                    .iter()
+                    .position(|d| {
+                        let mut d = d.clone();
+                        d.sort_unstable();
+                        d == c
+                    })
+                    .unwrap();
+                db.swap_remove(i);
+                continue;
+            }
+            // propagates the negation of the clause
+            let mut assigned: Vec<i64> = c.iter().map(|&x| -x).collect();
+            let conflict = loop {
+                let mut changed = false;
+                let mut conflict = false;
+                for d in db.iter() {
+                    if d.iter().any(|x| assigned.contains(x)) {
+                        continue;
+                    }
+                    let free: Vec<i64> = d
+                        .iter()
+                        .copied()
+                        .filter(|x| !assigned.contains(&-x))
+                        .collect();
+                    match free.len() {
+                        0 => conflict = true,
+                        1 => {
+                            assigned.push(free[0]);
+                            changed = true;
+                        }
+                        _ => {}
+                    }
+                }
+                if conflict || !changed {
+                    break conflict;
+                }
+            };
+            assert!(conflict, "clause {:?} is not RUP", c);
+            empty |= c.is_empty();
+            db.push(c);
+        }
+        assert!(empty);
+    }
+
+    #[test]
+    fn round_trip() {
+        let s = "c a comment\np cnf 4 3\n1 -2 0\n2 3\n-4 0 -1\n0\n%\n0\n";
+        let cnf = read_dimacs(s).unwrap();
+        assert_eq!(cnf.num_vars, 4);
+        assert_eq!(cnf.clauses[1], vec![Lit::pos(1), Lit::pos(2), Lit::neg(3)]);
+        assert_eq!(cnf.clauses[2], vec![Lit::neg(0)]);
+        let mut out = String::new();
+        write_dimacs(&cnf, &mut out).unwrap();
+        assert_eq!(out, "p cnf 4 3\n1 -2 0\n2 3 -4 0\n-1 0\n");
+        assert!(read_dimacs("p cnf 2 1\n1 3 0\n").is_err());
+        assert!(read_dimacs("p cnf 2 2\n1 2 0\n").is_err());
+
+        let mut cdcl = Cdcl::from(&cnf);
+        let res = cdcl.solve();
+        let mut out = String::new();
+        write_solution(res, cdcl.model(), &mut out).unwrap();
+        assert!(out.starts_with("s SATISFIABLE\nv "));
+        let values: Vec<bool> = out
+            .split_whitespace()
+            .filter_map(|t| t.parse::<i64>().ok())
+            .filter(|&x| x != 0)
+            .map(|x| x > 0)
+            .collect();
+        assert_eq!(cnf.num_violated(&values), 0);
+    }
+
+    #[test]
+    fn drat_proof() {
+        // 6 pigeons in 5 holes
+        let (p, h) = (6, 5);
+        let mut cnf = Cnf::new();
+        for i in 0..p {
+            cnf.add_clause(&(0..h).map(|j| Lit::pos(i * h + j)).collect::<Vec<_>>());
+        }
+        for j in 0..h {
+            for a in 0..p {
+                for b in a + 1..p {
+                    cnf.add_clause(&[Lit::neg(a * h + j), Lit::neg(b * h + j)]);
+                }
+            }
+        }
+        let mut cdcl = Cdcl::new();
+        cdcl.enable_proof();
+        for c in cnf.clauses.iter() {
+            cdcl.add_clause(c);
+        }
+        assert_eq!(cdcl.solve(), SatResult::Unsat);
+        check_rup(&cnf, cdcl.proof().unwrap());
+    }
+}
@@ -0,0 +1,502 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::sat::cnf::*;
+
+/// encodings of cardinality constraints, all of them keep arc consistency with unit propagation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardEncoding {
+    /// Sinz's sequential counter, O(n·k) clauses and variables
+    SequentialCounter,
+    /// totalizer truncated to k + 1 outputs, O(n·k) variables and O(n·k²) clauses
+    Totalizer,
+    /// cardinality network built from simplified odd-even merges, O(n·log²k) clauses and variables
+    CardinalityNetwork,
+}
+
+/// encodings of pseudo-Boolean constraints
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PbEncoding {
+    /// reduced ordered decision diagram, keeps arc consistency but can be exponential
+    Bdd,
+    /// binary adder and comparator, O(n·log(max weight)) clauses but weaker propagation
+    Adder,
+}
+
+/// outputs of a totalizer over the inputs, limited to max_outputs,
+/// the k-th output is implied by k + 1 true inputs
+pub fn totalizer<D: ClauseDb>(db: &mut D, inputs: &[Lit], max_outputs: usize) -> Vec<Lit> {
+    if inputs.len() <= 1 {
+        return inputs.to_vec();
+    }
+    let (a, b) = inputs.split_at(inputs.len() / 2);
+    let a = totalizer(db, a, max_outputs);
+    let b = totalizer(db, b, max_outputs);
+    let out: Vec<Lit> = (0..inputs.len().min(max_outputs))
+        .map(|_| Lit::pos(db.new_var()))
+        .collect();
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            if i + j == 0 || i + j > out.len() {
+                continue;
+            }
+            let mut c = vec![out[i + j - 1]];
+            if i > 0 {
+                c.push(!a[i - 1]);
+            }
+            if j > 0 {
+                c.push(!b[j - 1]);
+            }
+            db.add_clause(&c);
+        }
+    }
+    out
+}
+
+fn sequential_counter<D: ClauseDb>(db: &mut D, x: &[Lit], k: usize) {
+    let n = x.len();
+    // s[i][j] is implied by j + 1 true literals among x[..=i]
+    let s: Vec<Vec<Lit>> = (0..n - 1)
+        .map(|_| (0..k).map(|_| Lit::pos(db.new_var())).collect())
+        .collect();
+    for i in 0..n - 1 {
+        db.add_clause(&[!x[i], s[i][0]]);
+        if i == 0 {
+            continue;
+        }
+        for j in 0..k {
+            db.add_clause(&[!s[i - 1][j], s[i][j]]);
+            if j > 0 {
+                db.add_clause(&[!x[i], !s[i - 1][j - 1], s[i][j]]);
+            }
+        }
+        db.add_clause(&[!x[i], !s[i - 1][k - 1]]);
+    }
+    db.add_clause(&[!x[n - 1], !s[n - 2][k - 1]]);
+}
+
+/// wire of a sorting network, None is the constant false
+type Wire = Option<Lit>;
+
+/// sorts two wires, the first output is implied by any true input and the second one by both
+fn comparator<D: ClauseDb>(db: &mut D, a: Wire, b: Wire) -> (Wire, Wire) {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let (hi, lo) = (Lit::pos(db.new_var()), Lit::pos(db.new_var()));
+            db.add_clause(&[!a, hi]);
+            db.add_clause(&[!b, hi]);
+            db.add_clause(&[!a, !b, lo]);
+            (Some(hi), Some(lo))
+        }
+        (a, None) | (None, a) => (a, None),
+    }
+}
+
+fn odds(a: &[Wire]) -> Vec<Wire> {
+    a.iter().step_by(2).copied().collect()
+}
+fn evens(a: &[Wire]) -> Vec<Wire> {
+    a.iter().skip(1).step_by(2).copied().collect()
+}
+
+/// merges two sorted sequences of the same power of two length
+fn half_merge<D: ClauseDb>(db: &mut D, a: &[Wire], b: &[Wire]) -> Vec<Wire> {
+    let n = a.len();
+    if n == 1 {
+        let (c1, c2) = comparator(db, a[0], b[0]);
+        return vec![c1, c2];
+    }
+    let d = half_merge(db, &odds(a), &odds(b));
+    let e = half_merge(db, &evens(a), &evens(b));
+    let mut c = vec![d[0]];
+    for i in 0..n - 1 {
+        let (hi, lo) = comparator(db, d[i + 1], e[i]);
+        c.push(hi);
+        c.push(lo);
+    }
+    c.push(e[n - 1]);
+    c
+}
+
+fn half_sort<D: ClauseDb>(db: &mut D, a: &[Wire]) -> Vec<Wire> {
+    if a.len() == 1 {
+        return a.to_vec();
+    }
+    let (l, r) = a.split_at(a.len() / 2);
+    let (l, r) = (half_sort(db, l), half_sort(db, r));
+    half_merge(db, &l, &r)
+}
+
+/// first n + 1 outputs of the merge of two sorted sequences of the same power of two length n
+fn simplified_merge<D: ClauseDb>(db: &mut D, a: &[Wire], b: &[Wire]) -> Vec<Wire> {
+    let n = a.len();
+    if n == 1 {
+        let (c1, c2) = comparator(db, a[0], b[0]);
+        return vec![c1, c2];
+    }
+    let d = simplified_merge(db, &odds(a), &odds(b));
+    let e = simplified_merge(db, &evens(a), &evens(b));
+    let mut c = vec![d[0]];
+    for i in 0..n / 2 {
+        let (hi, lo) = comparator(db, d[i + 1], e[i]);
+        c.push(hi);
+        c.push(lo);
+    }
+    c
+}
+
+/// first m outputs of the sorted inputs, m a power of two dividing the number of inputs
+fn card_network<D: ClauseDb>(db: &mut D, a: &[Wire], m: usize) -> Vec<Wire> {
+    if a.len() == m {
+        return half_sort(db, a);
+    }
+    let (l, r) = a.split_at(m);
+    let l = card_network(db, l, m);
+    let r = card_network(db, r, m);
+    let mut c = simplified_merge(db, &l, &r);
+    c.truncate(m);
+    c
+}
+
+/// at most k of the literals are true
+pub fn at_most_k<D: ClauseDb>(db: &mut D, lits: &[Lit], k: usize, enc: CardEncoding) {
+    if k >= lits.len() {
+        return;
+    }
+    if k == 0 {
+        for &l in lits {
+            db.add_clause(&[!l]);
+        }
+        return;
+    }
+    match enc {
+        CardEncoding::SequentialCounter => sequential_counter(db, lits, k),
+        CardEncoding::Totalizer => {
+            let out = totalizer(db, lits, k + 1);
+            db.add_clause(&[!out[k]]);
+        }
+        CardEncoding::CardinalityNetwork => {
+            let m = (k + 1).next_power_of_two();
+            let mut wires: Vec<Wire> = lits.iter().map(|&l| Some(l)).collect();
+            wires.resize(lits.len().div_ceil(m) * m, None);
+            if let Some(l) = card_network(db, &wires, m)[k] {
+                db.add_clause(&[!l]);
+            }
+        }
+    }
+}
+
+/// at least k of the literals are true
+pub fn at_least_k<D: ClauseDb>(db: &mut D, lits: &[Lit], k: usize, enc: CardEncoding) {
+    if k > lits.len() {
+        db.add_clause(&[]);
+        return;
+    }
+    let neg: Vec<Lit> = lits.iter().map(|&l| !l).collect();
+    at_most_k(db, &neg, lits.len() - k, enc);
+}
+
+/// exactly k of the literals are true
+pub fn exactly_k<D: ClauseDb>(db: &mut D, lits: &[Lit], k: usize, enc: CardEncoding) {
+    at_most_k(db, lits, k, enc);
+    at_least_k(db, lits, k, enc);
+}
+
+/// at most one of the literals is true, pairwise for few literals and sequential counter otherwise
+pub fn at_most_one<D: ClauseDb>(db: &mut D, lits: &[Lit]) {
+    if lits.len() <= 5 {
+        for i in 0..lits.len() {
+            for j in i + 1..lits.len() {
+                db.add_clause(&[!lits[i], !lits[j]]);
+            }
+        }
+    } else {
+        at_most_k(db, lits, 1, CardEncoding::SequentialCounter);
+    }
+}
+
+pub fn exactly_one<D: ClauseDb>(db: &mut D, lits: &[Lit]) {
+    db.add_clause(lits);
+    at_most_one(db, lits);
+}
+
+/// node of the BDD of `sum of w_i·x_i for i >= first <= bound`, terms sorted by decreasing weight,
+/// Err with its value if the node is constant
+fn bdd_node<D: ClauseDb>(
+    db: &mut D,
+    terms: &[(Lit, u64)],
+    suffix: &[u64],
+    first: usize,
+    bound: u64,
+    memo: &mut HashMap<(usize, u64), Lit>,
+) -> Result<Lit, bool> {
+    if suffix[first] <= bound {
+        return Err(true);
+    }
+    // terms are smaller than the bound, so first < terms.len()
+    if let Some(&node) = memo.get(&(first, bound)) {
+        return Ok(node);
+    }
+    let (x, w) = terms[first];
+    let hi = match bound.checked_sub(w) {
+        Some(b) => bdd_node(db, terms, suffix, first + 1, b, memo),
+        None => Err(false),
+    };
+    let lo = bdd_node(db, terms, suffix, first + 1, bound, memo);
+    let node = Lit::pos(db.new_var());
+    match hi {
+        Ok(hi) => db.add_clause(&[!node, !x, hi]),
+        Err(false) => db.add_clause(&[!node, !x]),
+        Err(true) => {}
+    }
+    match lo {
+        Ok(lo) => db.add_clause(&[!node, lo]),
+        Err(false) => db.add_clause(&[!node]),
+        Err(true) => {}
+    }
+    memo.insert((first, bound), node);
+    Ok(node)
+}
+
+/// full or half adder, returns the sum and carry of the inputs
+fn adder<D: ClauseDb>(db: &mut D, inputs: &[Lit]) -> (Lit, Lit) {
+    let s = Lit::pos(db.new_var());
+    let c = Lit::pos(db.new_var());
+    let n = inputs.len();
+    // sum is the parity of the inputs
+    for mask in 0..1u32 << n {
+        let mut clause: Vec<Lit> = (0..n)
+            .map(|i| {
+                if mask >> i & 1 == 1 {
+                    !inputs[i]
+                } else {
+                    inputs[i]
+                }
+            })
+            .collect();
+        clause.push(if !mask.count_ones().is_multiple_of(2) {
+            s
+        } else {
+            !s
+        });
+        db.add_clause(&clause);
+    }
+    // carry is true iff at least two inputs are
+    for i in 0..n {
+        for j in i + 1..n {
+            db.add_clause(&[!inputs[i], !inputs[j], c]);
+        }
+    }
+    for skip in 0..n {
+        let mut clause: Vec<Lit> = (0..n).filter(|&i| i != skip).map(|i| inputs[i]).collect();
+        clause.push(!c);
+        db.add_clause(&clause);
+    }
+    (s, c)
+}
+
+/// the sum of the weights of the true literals is at most k
+pub fn pb_at_most<D: ClauseDb>(db: &mut D, terms: &[(Lit, u64)], k: u64, enc: PbEncoding) {
+    let mut terms: Vec<(Lit, u64)> = terms.iter().copied().filter(|t| t.1 > 0).collect();
+    for &(l, _) in terms.iter().filter(|t| t.1 > k) {
+        db.add_clause(&[!l]);
+    }
+    terms.retain(|t| t.1 <= k);
+    if terms.iter().map(|t| t.1).sum::<u64>() <= k {
+        return;
+    }
+    match enc {
+        PbEncoding::Bdd => {
+            terms.sort_by_key(|t| std::cmp::Reverse(t.1));
+            let mut suffix = vec![0; terms.len() + 1];
+            for i in (0..terms.len()).rev() {
+                suffix[i] = suffix[i + 1] + terms[i].1;
+            }
+            let root = bdd_node(db, &terms, &suffix, 0, k, &mut HashMap::new());
+            match root {
+                Ok(root) => db.add_clause(&[root]),
+                Err(false) => db.add_clause(&[]),
+                Err(true) => {}
+            }
+        }
+        PbEncoding::Adder => {
+            let mut buckets = Vec::<VecDeque<Lit>>::new();
+            for &(l, w) in terms.iter() {
+                for bit in 0..64 {
+                    if w >> bit & 1 == 1 {
+                        if buckets.len() <= bit {
+                            buckets.resize(bit + 1, VecDeque::new());
+                        }
+                        buckets[bit].push_back(l);
+                    }
+                }
+            }
+            // binary digits of the sum, least significant first
+            let mut sum = Vec::<Option<Lit>>::new();
+            let mut bit = 0;
+            while bit < buckets.len() {
+                while buckets[bit].len() >= 2 {
+                    let n = buckets[bit].len().min(3);
+                    let inputs: Vec<Lit> = buckets[bit].drain(..n).collect();
+                    let (s, c) = adder(db, &inputs);
+                    buckets[bit].push_back(s);
+                    if buckets.len() <= bit + 1 {
+                        buckets.push(VecDeque::new());
+                    }
+                    buckets[bit + 1].push_back(c);
+                }
+                sum.push(buckets[bit].pop_front());
+                bit += 1;
+            }
+            // sum > k iff at a zero bit of k the sum has a one and all higher bits are equal,
+            // so each one of the sum at a zero bit of k needs a zero at a higher one bit of k,
+            // a constant zero there already makes the sum smaller than k
+            'bits: for (i, s) in sum.iter().enumerate() {
+                let Some(s) = *s else {
+                    continue;
+                };
+                if i < 64 && k >> i & 1 == 1 {
+                    continue;
+                }
+                let mut clause = vec![!s];
+                for (j, t) in sum.iter().enumerate().skip(i + 1) {
+                    if j < 64 && k >> j & 1 == 1 {
+                        match t {
+                            Some(t) => clause.push(!*t),
+                            None => continue 'bits,
+                        }
+                    }
+                }
+                db.add_clause(&clause);
+            }
+        }
+    }
+}
+
+/// the sum of the weights of the true literals is at least k
+pub fn pb_at_least<D: ClauseDb>(db: &mut D, terms: &[(Lit, u64)], k: u64, enc: PbEncoding) {
+    let total: u64 = terms.iter().map(|t| t.1).sum();
+    if k > total {
+        db.add_clause(&[]);
+        return;
+    }
+    let neg: Vec<(Lit, u64)> = terms.iter().map(|&(l, w)| (!l, w)).collect();
+    pb_at_most(db, &neg, total - k, enc);
+}
+
+/// the sum of the weights of the true literals is exactly k
+pub fn pb_exactly<D: ClauseDb>(db: &mut D, terms: &[(Lit, u64)], k: u64, enc: PbEncoding) {
+    pb_at_most(db, terms, k, enc);
+    pb_at_least(db, terms, k, enc);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::*;
+    use crate::sat::cdcl::*;
+
+    /// checks that the encoding accepts exactly the assignments of the n first variables satisfying f
+    fn check<E: Fn(&mut Cdcl), F: Fn(&[bool]) -> bool>(n: usize, encode: E, f: F) {
+        let mut cdcl = Cdcl::new();
+        for _ in 0..n {
+            cdcl.new_var();
+        }
+        encode(&mut cdcl);
+        for code in 0..1u32 << n {
+            let values: Vec<bool> = (0..n).map(|i| code >> i & 1 == 1).collect();
+            let assumptions: Vec<Lit> = (0..n).map(|i| Lit::new(i, values[i])).collect();
+            let res = cdcl.solve_assuming(&assumptions);
+            let expected = if f(&values) {
+                SatResult::Sat
+            } else {
+                SatResult::Unsat
+            };
+            assert_eq!(res, expected, "{:?}", values);
+        }
+    }
+
+    #[test]
+    fn cardinality() {
+        let encs = [
+            CardEncoding::SequentialCounter,
+            CardEncoding::Totalizer,
+            CardEncoding::CardinalityNetwork,
+        ];
+        for n in 1..=7 {
+            let lits: Vec<Lit> = (0..n).map(|i| Lit::new(i, i % 3 != 1)).collect();
+            let count = |v: &[bool]| lits.iter().filter(|l| l.eval(v)).count();
+            for k in 0..=n + 1 {
+                for enc in encs {
+                    check(n, |db| at_most_k(db, &lits, k, enc), |v| count(v) <= k);
+                    check(n, |db| at_least_k(db, &lits, k, enc), |v| count(v) >= k);
+                    check(n, |db| exactly_k(db, &lits, k, enc), |v| count(v) == k);
+                }
+            }
+            check(n, |db| at_most_one(db, &lits), |v| count(v) <= 1);
+            check(n, |db| exactly_one(db, &lits), |v| count(v) == 1);
+        }
+    }
+
+    #[test]
+    fn pseudo_boolean() {
+        let mut rng = Splitmix64::from_u64(3);
+        for _ in 0..40 {
+            let n = 6;
+            let terms: Vec<(Lit, u64)> = (0..n)
+                .map(|i| {
+                    (
+                        Lit::new(i, rng.next_u64().is_multiple_of(2)),
+                        rng.next_u64() % 9,
+                    )
+                })
+                .collect();
+            let sum =
+                |v: &[bool]| -> u64 { terms.iter().filter(|t| t.0.eval(v)).map(|t| t.1).sum() };
+            let k = rng.next_u64() % 30;
+            for enc in [PbEncoding::Bdd, PbEncoding::Adder] {
+                check(n, |db| pb_at_most(db, &terms, k, enc), |v| sum(v) <= k);
+                check(n, |db| pb_at_least(db, &terms, k, enc), |v| sum(v) >= k);
+                check(n, |db| pb_exactly(db, &terms, k, enc), |v| sum(v) == k);
+            }
+        }
+    }
+
+    #[test]
+    fn pseudo_boolean_sparse_weights() {
+        // the sum has no bit where k has one above a zero bit of k
+        let terms: Vec<(Lit, u64)> = vec![(Lit::pos(0), 1), (Lit::pos(1), 8), (Lit::pos(2), 8)];
+        let sum = |v: &[bool]| -> u64 { terms.iter().filter(|t| t.0.eval(v)).map(|t| t.1).sum() };
+        for k in 0..=18 {
+            for enc in [PbEncoding::Bdd, PbEncoding::Adder] {
+                check(3, |db| pb_at_most(db, &terms, k, enc), |v| sum(v) <= k);
+                check(3, |db| pb_at_least(db, &terms, k, enc), |v| sum(v) >= k);
+            }
+        }
+        let mut rng = Splitmix64::from_u64(5);
+        for _ in 0..40 {
+            let n = 5;
+            let terms: Vec<(Lit, u64)> = (0..n)
+                .map(|i| {
+                    (
+                        Lit::new(i, rng.next_u64().is_multiple_of(2)),
+                        1 << (rng.next_u64() % 5),
+                    )
+                })
+                .collect();
+            let sum =
+                |v: &[bool]| -> u64 { terms.iter().filter(|t| t.0.eval(v)).map(|t| t.1).sum() };
+            let k = rng.next_u64() % 40;
+            check(
+                n,
+                |db| pb_at_most(db, &terms, k, PbEncoding::Adder),
+                |v| sum(v) <= k,
+            );
+        }
+    }
+
+    #[test]
+    fn totalizer_without_inputs() {
+        let mut cdcl = Cdcl::new();
+        assert!(totalizer(&mut cdcl, &[], 3).is_empty());
+    }
+}
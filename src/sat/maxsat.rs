@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::core::*;
+use crate::sat::cdcl::*;
+use crate::sat::cnf::*;
+use crate::sat::encodings::totalizer;
+
+/// weighted partial MaxSAT: hard clauses must be satisfied and the objective
+/// is the total weight of the violated soft clauses
+#[derive(Clone, Debug, Default)]
+pub struct MaxSat {
+    pub num_vars: usize,
+    pub hard: Vec<Vec<Lit>>,
+    pub soft: Vec<(Vec<Lit>, u64)>,
+}
+impl MaxSat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn new_var(&mut self) -> usize {
+        self.num_vars += 1;
+        self.num_vars - 1
+    }
+    fn use_vars(&mut self, clause: &[Lit]) {
+        if let Some(max) = clause.iter().map(|l| l.var()).max() {
+            self.num_vars = self.num_vars.max(max + 1);
+        }
+    }
+    /// adds the hard clause, creating the variables it uses if needed
+    pub fn add_hard(&mut self, clause: &[Lit]) {
+        self.use_vars(clause);
+        self.hard.push(clause.to_vec());
+    }
+    /// adds the soft clause, creating the variables it uses if needed
+    pub fn add_soft(&mut self, clause: &[Lit], weight: u64) {
+        self.use_vars(clause);
+        self.soft.push((clause.to_vec(), weight));
+    }
+    /// total weight of the violated soft clauses
+    pub fn violated_weight(&self, values: &[bool]) -> u64 {
+        self.soft
+            .iter()
+            .filter(|(c, _)| !c.iter().any(|l| l.eval(values)))
+            .map(|(_, w)| w)
+            .sum()
+    }
+}
+/// clauses added through ClauseDb are hard
+impl ClauseDb for MaxSat {
+    fn new_var(&mut self) -> usize {
+        MaxSat::new_var(self)
+    }
+    fn add_clause(&mut self, clause: &[Lit]) {
+        self.add_hard(clause)
+    }
+}
+impl Problem for MaxSat {
+    type Sol = Vec<bool>;
+    type Obj = SatObj;
+    fn obj(&self, sol: &Self::Sol) -> Self::Obj {
+        if self.is_feasible(sol) {
+            SatObj(self.violated_weight(sol) as i64)
+        } else {
+            SatObj::unfeas()
+        }
+    }
+    fn is_feasible(&self, sol: &Self::Sol) -> bool {
+        sol.len() == self.num_vars && self.hard.iter().all(|c| c.iter().any(|l| l.eval(sol)))
+    }
+}
+impl AsRef<MaxSat> for MaxSat {
+    fn as_ref(&self) -> &MaxSat {
+        self
+    }
+}
+impl Reduction<MaxSat> for MaxSat {
+    fn reduce_from(p: &MaxSat) -> Self {
+        p.clone()
+    }
+    fn lift_solution_to(&self, sol: Self::Sol) -> Vec<bool> {
+        sol
+    }
+    fn lift_obj_to(&self, obj: Self::Obj) -> SatObj {
+        obj
+    }
+}
+
+/// core guided MaxSAT solver in the style of OLL/RC2 with stratification on the weights:
+/// each core found under the assumptions raises the lower bound by its minimum weight and is
+/// relaxed with a totalizer, the solutions found at each weight level are reported.
+/// solves any problem that is a MaxSat instance with the same objective
+#[derive(Clone, Default)]
+pub struct Oll;
+impl<P> Solver<P> for Oll
+where
+    P: Problem<Sol = Vec<bool>, Obj = SatObj> + AsRef<MaxSat>,
+{
+    fn solve<SK: SolutionKeeper<P>, S: StopCondition<SatObj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let p = p.as_ref();
+        let mut cdcl = Cdcl::new();
+        while cdcl.num_vars() < p.num_vars {
+            cdcl.new_var();
+        }
+        for c in p.hard.iter() {
+            if !cdcl.add_clause(c) {
+                sk.add_dual_bound(SatObj::unfeas());
+                return;
+            }
+        }
+        let mut lb = 0u64;
+        // weight paid when each assumption is false
+        let mut weights = HashMap::<Lit, u64>::new();
+        for (c, w) in p.soft.iter().filter(|s| s.1 > 0) {
+            match c.len() {
+                0 => lb += w,
+                1 => *weights.entry(c[0]).or_default() += w,
+                _ => {
+                    let b = Lit::pos(cdcl.new_var());
+                    let mut c = c.clone();
+                    c.push(!b);
+                    cdcl.add_clause(&c);
+                    *weights.entry(b).or_default() += w;
+                }
+            }
+        }
+        // negated totalizer outputs used as assumptions, with the outputs and the index of the output
+        let mut sums = HashMap::<Lit, (Rc<Vec<Lit>>, usize)>::new();
+        let mut threshold = weights.values().copied().max().unwrap_or(0);
+        sk.add_dual_bound(SatObj(lb as i64));
+        loop {
+            let mut assumptions: Vec<Lit> = weights
+                .iter()
+                .filter(|&(_, &w)| w >= threshold)
+                .map(|(&l, _)| l)
+                .collect();
+            assumptions.sort();
+            let res = cdcl.solve_assuming_with(&assumptions, || {
+                sk.iter();
+                stop.stop(sk.best_obj(), SatObj(lb as i64))
+            });
+            match res {
+                SatResult::Unknown => return,
+                SatResult::Sat => {
+                    let sol = cdcl.model()[..p.num_vars].to_vec();
+                    let obj = p.obj(&sol);
+                    sk.add_solution(&sol, obj);
+                    match weights.values().copied().filter(|&w| w < threshold).max() {
+                        Some(w) => threshold = w,
+                        None => {
+                            debug_assert_eq!(obj, SatObj(lb as i64));
+                            sk.add_dual_bound(obj);
+                            return;
+                        }
+                    }
+                }
+                SatResult::Unsat => {
+                    let core = cdcl.unsat_core().to_vec();
+                    if core.is_empty() {
+                        sk.add_dual_bound(SatObj::unfeas());
+                        return;
+                    }
+                    let m = core.iter().map(|l| weights[l]).min().unwrap();
+                    lb += m;
+                    sk.add_dual_bound(SatObj(lb as i64));
+                    for l in core.iter() {
+                        let w = weights.get_mut(l).unwrap();
+                        *w -= m;
+                        if *w == 0 {
+                            weights.remove(l);
+                        }
+                        // the sum now pays m for one more violation
+                        if let Some((out, k)) = sums.get(l).cloned() {
+                            if k + 1 < out.len() {
+                                *weights.entry(!out[k + 1]).or_default() += m;
+                                sums.insert(!out[k + 1], (out, k + 1));
+                            }
+                        }
+                    }
+                    if core.len() > 1 {
+                        let violated: Vec<Lit> = core.iter().map(|&l| !l).collect();
+                        let out = Rc::new(totalizer(&mut cdcl, &violated, violated.len()));
+                        // at least one is violated, a second one costs m more
+                        *weights.entry(!out[1]).or_default() += m;
+                        sums.insert(!out[1], (out, 1));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_clause(rng: &mut Splitmix64, n: usize) -> Vec<Lit> {
+        let len = 1 + rng.next_u64() as usize % 3;
+        (0..len)
+            .map(|_| {
+                Lit::new(
+                    rng.next_u64() as usize % n,
+                    rng.next_u64().is_multiple_of(2),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Splitmix64::from_u64(21);
+        for it in 0..100 {
+            let n = 8;
+            let mut p = MaxSat::new();
+            p.num_vars = n;
+            for _ in 0..it % 12 {
+                let c = random_clause(&mut rng, n);
+                p.add_hard(&c);
+            }
+            for _ in 0..20 {
+                let c = random_clause(&mut rng, n);
+                p.add_soft(&c, 1 + rng.next_u64() % 5);
+            }
+            let best = (0..1u32 << n)
+                .map(|code| p.obj(&(0..n).map(|i| code >> i & 1 == 1).collect()))
+                .min()
+                .unwrap();
+
+            let mut sk = SimpleSolutionKeeper::<MaxSat>::default();
+            let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+            Oll.solve(p.clone(), &mut sk, stop);
+            assert_eq!(sk.best_obj(), best);
+            assert_eq!(sk.dual_bound, best);
+            if let Some((sol, obj)) = sk.best_solution() {
+                assert_eq!(p.obj(&sol), obj);
+            }
+        }
+    }
+}
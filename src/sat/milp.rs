@@ -0,0 +1,359 @@
+use std::fmt;
+
+use crate::core::*;
+use crate::lp::milp::*;
+use crate::lp::model::VariableId;
+use crate::sat::cnf::*;
+use crate::sat::encodings::*;
+use crate::sat::maxsat::MaxSat;
+
+/// tolerance on integrality of the scaled coefficients and bounds
+const INT_TOL: f64 = 1e-9;
+/// largest power of ten used to make the coefficients integral
+const MAX_SCALE: f64 = 1e6;
+/// pseudo-Boolean constraints use a BDD if the number of terms times the bound is at most this
+const BDD_LIMIT: u64 = 100_000;
+
+/// smallest power of ten making all the values integral, MAX_SCALE if there is none
+fn integral_scale(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let mut scale = 1.0;
+    while scale < MAX_SCALE
+        && values
+            .clone()
+            .any(|v| (v * scale - (v * scale).round()).abs() > INT_TOL * (v * scale).abs().max(1.0))
+    {
+        scale *= 10.0;
+    }
+    scale
+}
+
+/// MaxSAT instance equivalent to a MILP whose variables are all binary: variable i of the
+/// model is the boolean variable i, the constraints are hard pseudo-Boolean constraints and
+/// the objective is given by soft unit clauses.
+/// coefficients are scaled by a power of ten to make them integral, those needing more than
+/// 6 decimals are rounded so that the objective and the constraints are then approximate
+#[derive(Clone, Debug)]
+pub struct MilpMaxSat {
+    pub maxsat: MaxSat,
+    num_model_vars: usize,
+    /// the MILP objective is the violated weight divided by scale plus offset
+    scale: f64,
+    offset: f64,
+}
+impl AsRef<MaxSat> for MilpMaxSat {
+    fn as_ref(&self) -> &MaxSat {
+        &self.maxsat
+    }
+}
+impl Problem for MilpMaxSat {
+    type Sol = Vec<bool>;
+    type Obj = SatObj;
+    fn obj(&self, sol: &Self::Sol) -> Self::Obj {
+        self.maxsat.obj(sol)
+    }
+    fn is_feasible(&self, sol: &Self::Sol) -> bool {
+        self.maxsat.is_feasible(sol)
+    }
+}
+
+/// adds lo <= sum of the terms <= hi, bounds already scaled
+fn add_linear(db: &mut MaxSat, terms: &[(usize, f64)], lo: f64, hi: f64, bdd_limit: u64) {
+    // a·x = |a|·¬x + a for a negative coefficient
+    let mut shift = 0.0;
+    let terms: Vec<(Lit, u64)> = terms
+        .iter()
+        .filter(|t| t.1 != 0.0)
+        .map(|&(v, a)| {
+            if a < 0.0 {
+                shift += a;
+            }
+            (Lit::new(v, a > 0.0), a.abs().round() as u64)
+        })
+        .collect();
+    let total: u64 = terms.iter().map(|t| t.1).sum();
+    let cardinality = terms.windows(2).all(|w| w[0].1 == w[1].1);
+    let lits: Vec<Lit> = terms.iter().map(|t| t.0).collect();
+    let unit = terms.first().map_or(1, |t| t.1);
+    let enc = |k: u64| {
+        if terms.len() as u64 * k <= bdd_limit {
+            PbEncoding::Bdd
+        } else {
+            PbEncoding::Adder
+        }
+    };
+    if hi < f64::INFINITY {
+        let k = (hi - shift + INT_TOL).floor();
+        if k < 0.0 {
+            db.add_hard(&[]);
+        } else if k < total as f64 {
+            let k = k as u64;
+            if cardinality {
+                at_most_k(db, &lits, (k / unit) as usize, CardEncoding::Totalizer);
+            } else {
+                pb_at_most(db, &terms, k, enc(k));
+            }
+        }
+    }
+    if lo > f64::NEG_INFINITY {
+        let k = (lo - shift - INT_TOL).ceil();
+        if k > total as f64 {
+            db.add_hard(&[]);
+        } else if k > 0.0 {
+            let k = k as u64;
+            if cardinality {
+                at_least_k(
+                    db,
+                    &lits,
+                    k.div_ceil(unit) as usize,
+                    CardEncoding::Totalizer,
+                );
+            } else {
+                pb_at_least(db, &terms, k, enc(total - k));
+            }
+        }
+    }
+}
+
+/// a variable of the model that is neither binary nor removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotBinary(pub VariableId);
+impl fmt::Display for NotBinary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "variable {:?} is not binary", self.0)
+    }
+}
+impl std::error::Error for NotBinary {}
+
+impl MilpMaxSat {
+    /// first variable that is not integer with bounds in 0..=1, removed variables are fixed to 0
+    fn not_binary(p: &MilpProblem) -> Option<VariableId> {
+        p.model
+            .variables()
+            .iter()
+            .position(|v| !(v.removed || v.integer && v.lb.ceil() >= 0.0 && v.ub.floor() <= 1.0))
+            .map(VariableId)
+    }
+    /// whether the problem can be reduced, i.e. all its variables are binary or removed
+    pub fn is_binary(p: &MilpProblem) -> bool {
+        Self::not_binary(p).is_none()
+    }
+    /// reduction where the pseudo-Boolean constraints with more than bdd_limit terms times bound
+    /// use the adder encoding
+    fn reduce_with_bdd_limit(p: &MilpProblem, bdd_limit: u64) -> Self {
+        if let Some(vid) = Self::not_binary(p) {
+            panic!("{}", NotBinary(vid));
+        }
+        let model = &p.model;
+        let mut maxsat = MaxSat::new();
+        maxsat.num_vars = model.variables().len();
+        for (i, v) in model.variables().iter().enumerate() {
+            if v.removed {
+                maxsat.add_hard(&[Lit::neg(i)]);
+                continue;
+            }
+            let (lb, ub) = (v.lb.ceil(), v.ub.floor());
+            if lb > ub {
+                maxsat.add_hard(&[]);
+            } else if lb == 1.0 {
+                maxsat.add_hard(&[Lit::pos(i)]);
+            } else if ub == 0.0 {
+                maxsat.add_hard(&[Lit::neg(i)]);
+            }
+        }
+        for c in model.constraints().iter() {
+            let vars = &c.expr.variables;
+            let scale = integral_scale(vars.iter().map(|t| t.1));
+            let terms: Vec<(usize, f64)> = vars.iter().map(|&(v, a)| (v, a * scale)).collect();
+            let (lo, hi) = c.row_bounds();
+            add_linear(&mut maxsat, &terms, lo * scale, hi * scale, bdd_limit);
+        }
+        let sign = model.sense.sign();
+        let objective = &model.objective;
+        let scale = integral_scale(objective.variables.iter().map(|t| t.1));
+        let mut offset = sign * objective.constant;
+        for &(v, a) in objective.variables.iter() {
+            let a = sign * a;
+            let w = (a.abs() * scale).round() as u64;
+            if a < 0.0 {
+                // a·x = a + |a|·(1 - x)
+                offset += a;
+                maxsat.add_soft(&[Lit::pos(v)], w);
+            } else {
+                maxsat.add_soft(&[Lit::neg(v)], w);
+            }
+        }
+        Self {
+            maxsat,
+            num_model_vars: model.variables().len(),
+            scale,
+            offset,
+        }
+    }
+}
+
+impl TryFrom<&MilpProblem> for MilpMaxSat {
+    type Error = NotBinary;
+    fn try_from(p: &MilpProblem) -> Result<Self, NotBinary> {
+        match Self::not_binary(p) {
+            Some(vid) => Err(NotBinary(vid)),
+            None => Ok(Self::reduce_from(p)),
+        }
+    }
+}
+
+/// requires MilpMaxSat::is_binary, panics otherwise (use try_from to get an error instead),
+/// removed variables are fixed to 0
+impl Reduction<MilpProblem> for MilpMaxSat {
+    fn reduce_from(p: &MilpProblem) -> Self {
+        Self::reduce_with_bdd_limit(p, BDD_LIMIT)
+    }
+    fn lift_solution_to(&self, sol: Self::Sol) -> Vec<f64> {
+        sol[..self.num_model_vars]
+            .iter()
+            .map(|&b| if b { 1.0 } else { 0.0 })
+            .collect()
+    }
+    fn lift_obj_to(&self, obj: Self::Obj) -> MilpObj {
+        if !obj.is_feas() {
+            MilpObj::unfeas()
+        } else if !obj.is_bounded() {
+            MilpObj::unbounded()
+        } else {
+            MilpObj(obj.0 as f64 / self.scale + self.offset)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lp::model::*;
+    use crate::sat::cdcl::*;
+    use crate::sat::maxsat::Oll;
+
+    /// checks that the hard clauses accept exactly the feasible assignments
+    fn check_hard_clauses(p: &MilpProblem, reduced: &MilpMaxSat, n: usize) {
+        let mut cdcl = Cdcl::new();
+        for _ in 0..reduced.maxsat.num_vars {
+            cdcl.new_var();
+        }
+        for c in reduced.maxsat.hard.iter() {
+            cdcl.add_clause(c);
+        }
+        for code in 0..1u32 << n {
+            let x: Vec<f64> = (0..n).map(|i| (code >> i & 1) as f64).collect();
+            let assumptions: Vec<Lit> = (0..n).map(|i| Lit::new(i, x[i] == 1.0)).collect();
+            let expected = if p.obj(&x).is_feas() {
+                SatResult::Sat
+            } else {
+                SatResult::Unsat
+            };
+            assert_eq!(cdcl.solve_assuming(&assumptions), expected, "{:?}", x);
+        }
+    }
+
+    /// compares the optimum of random binary models with brute force
+    fn check_brute_force(bdd_limit: u64, coefs: &[f64]) {
+        let mut rng = Splitmix64::from_u64(12);
+        let coef = |rng: &mut Splitmix64| coefs[rng.next_u64() as usize % coefs.len()];
+        for it in 0..100u32 {
+            let n = 7;
+            let mut mb = ModelBuilder::new();
+            let x: Vec<VariableId> = (0..n)
+                .map(|i| mb.add_var(0.0, 1.0, true, format!("x{}", i)))
+                .collect();
+            for _ in 0..it % 5 {
+                let mut e = Expression::cons(0.0);
+                for &v in x.iter() {
+                    e.set_coefficient(v, coef(&mut rng));
+                }
+                let b = coef(&mut rng);
+                mb.add_constraint(match rng.next_u64() % 3 {
+                    0 => e.between(f64::NEG_INFINITY, b),
+                    1 => e.between(b, f64::INFINITY),
+                    _ => e.between(b - 1.0, b + 0.5),
+                });
+            }
+            let mut obj = Expression::cons(coef(&mut rng));
+            for &v in x.iter() {
+                obj.set_coefficient(v, coef(&mut rng) * 1.25);
+            }
+            let sense = if it.is_multiple_of(2) {
+                Sense::Minimize
+            } else {
+                Sense::Maximize
+            };
+            mb.set_objective(sense, obj);
+            if it % 7 == 3 {
+                mb.remove_var(x[2]);
+            }
+            let p = MilpProblem::from(mb);
+            let best = (0..1u32 << n)
+                .map(|code| p.obj(&(0..n).map(|i| (code >> i & 1) as f64).collect()))
+                .min()
+                .unwrap();
+
+            let reduced = MilpMaxSat::reduce_with_bdd_limit(&p, bdd_limit);
+            check_hard_clauses(&p, &reduced, n);
+            let mut sk = SimpleSolutionKeeper::<MilpMaxSat>::default();
+            let stop = TimeStop::new(StdTimer, std::time::Duration::from_secs(10));
+            Oll.solve(reduced.clone(), &mut sk, stop);
+            match sk.best_solution() {
+                Some((sol, obj)) => {
+                    let lifted = reduced.lift_obj_to(obj);
+                    assert!((lifted.0 - best.0).abs() < 1e-9, "{:?} {:?}", lifted, best);
+                    let direct = p.obj(&reduced.lift_solution_to(sol));
+                    assert!((direct.0 - lifted.0).abs() < 1e-9);
+                }
+                None => assert!(!best.is_feas()),
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let coefs: Vec<f64> = (0..9).map(|i| i as f64 * 0.5 - 2.0).collect();
+        check_brute_force(BDD_LIMIT, &coefs);
+    }
+
+    #[test]
+    fn matches_brute_force_with_adders() {
+        // sparse bits in the weights
+        check_brute_force(0, &[-4.0, 0.0, 0.0, 0.0, 0.5, 4.0]);
+        let coefs: Vec<f64> = (0..9).map(|i| i as f64 * 0.5 - 2.0).collect();
+        check_brute_force(0, &coefs);
+    }
+
+    #[test]
+    fn adder_with_sparse_weights() {
+        // the sum of the weights has no bit 1, which is one in 6
+        let mut mb = ModelBuilder::new();
+        let x: Vec<VariableId> = (0..3)
+            .map(|i| mb.add_var(0.0, 1.0, true, format!("x{}", i)))
+            .collect();
+        let mut e = Expression::cons(0.0);
+        for (&v, a) in x.iter().zip([4.0, 1.0, 4.0]) {
+            e.set_coefficient(v, a);
+        }
+        mb.add_constraint(e.between(f64::NEG_INFINITY, 6.0));
+        let p = MilpProblem::from(mb);
+        check_hard_clauses(&p, &MilpMaxSat::reduce_with_bdd_limit(&p, 0), 3);
+    }
+
+    #[test]
+    fn not_binary() {
+        let mut mb = ModelBuilder::new();
+        let x = mb.add_var(0.0, 1.0, true, "x".to_string());
+        let y = mb.add_var(0.0, 2.0, true, "y".to_string());
+        let z = mb.add_var(0.0, 1.0, false, "z".to_string());
+        mb.minimize(x + y + z);
+        let p = MilpProblem::from(mb.clone());
+        assert!(!MilpMaxSat::is_binary(&p));
+        assert_eq!(MilpMaxSat::try_from(&p).unwrap_err(), NotBinary(y));
+        mb.remove_var(y);
+        mb.set_integer(z, true);
+        let p = MilpProblem::from(mb);
+        assert!(MilpMaxSat::is_binary(&p));
+        assert!(MilpMaxSat::try_from(&p).is_ok());
+    }
+}
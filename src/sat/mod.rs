@@ -1 +1,6 @@
-
+pub mod cdcl;
+pub mod cnf;
+pub mod dimacs;
+pub mod encodings;
+pub mod maxsat;
+pub mod milp;
@@ -0,0 +1,267 @@
+use crate::core::*;
+use crate::solver::simulated_annealing::CoolingSchedule;
+use neighbour_space::*;
+
+/// rule deciding whether a random neighbour replaces the current solution
+pub trait Acceptance: Clone {
+    /// called at the start of each iteration with the progress reported by the stop condition,
+    /// returning false stops the search
+    fn next_iteration(&mut self, _current: f64, _progress: Option<f64>) -> bool {
+        true
+    }
+    /// whether the neighbour is accepted, the move is made if it is
+    fn accept<R: rng::Rng>(&mut self, current: f64, candidate: f64, best: f64, rng: &mut R)
+        -> bool;
+}
+
+/// local search that draws a random neighbour at each iteration and moves to it
+/// if the acceptance criterion allows it, the initial solution is given to the solution keeper
+/// first so that the best objective is known by the acceptance criterion from the start
+#[derive(Clone)]
+pub struct AcceptanceSearch<
+    P: Problem,
+    N: NeighbourhoodIndirectRandom<P>,
+    R: rng::Rng,
+    A: Acceptance,
+> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    rng: R,
+    acceptance: A,
+    ns: N,
+    report_initial: bool,
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng, A: Acceptance>
+    AcceptanceSearch<P, N, R, A>
+{
+    pub fn new(ns: N, initial_solution: (P::Sol, P::Obj), acceptance: A, rng: R) -> Self {
+        Self {
+            initial_solution: Some(initial_solution),
+            rng,
+            acceptance,
+            ns,
+            report_initial: true,
+        }
+    }
+    /// does not give the initial solution to the solution keeper, as SimulatedAnnealing always did
+    pub(crate) fn without_initial_report(mut self) -> Self {
+        self.report_initial = false;
+        self
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng, A: Acceptance> Solver<P>
+    for AcceptanceSearch<P, N, R, A>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
+        if self.report_initial {
+            sk.add_solution(&current_solution, current_obj);
+        }
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            if !self
+                .acceptance
+                .next_iteration(current_obj.into(), stop.progress())
+            {
+                break;
+            }
+            let nid = self
+                .ns
+                .random_neighbour_id(&p, &current_solution, &mut self.rng);
+            let nobj = self.ns.neighbour_obj(&p, &current_solution, &nid);
+            if self.acceptance.accept(
+                current_obj.into(),
+                nobj.into(),
+                sk.best_obj().into(),
+                &mut self.rng,
+            ) {
+                current_solution = self.ns.random_neighbour(&p, current_solution, nid);
+                current_obj = nobj;
+                sk.add_solution(&current_solution, current_obj);
+            }
+        }
+    }
+}
+
+/// accepts neighbours not worse than the current solution
+#[derive(Clone, Default)]
+pub struct HillClimbingAcceptance;
+impl Acceptance for HillClimbingAcceptance {
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        _best: f64,
+        _rng: &mut R,
+    ) -> bool {
+        candidate <= current
+    }
+}
+
+/// accepts neighbours not worse than the water level or than the current solution,
+/// the level starts at initial_level and goes down by rain_speed at each iteration
+#[derive(Clone)]
+pub struct GreatDelugeAcceptance {
+    level: f64,
+    rain_speed: f64,
+}
+impl GreatDelugeAcceptance {
+    pub fn new(initial_level: f64, rain_speed: f64) -> Self {
+        debug_assert!(rain_speed >= 0.0);
+        Self {
+            level: initial_level,
+            rain_speed,
+        }
+    }
+}
+impl Acceptance for GreatDelugeAcceptance {
+    fn next_iteration(&mut self, _current: f64, _progress: Option<f64>) -> bool {
+        self.level -= self.rain_speed;
+        true
+    }
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        _best: f64,
+        _rng: &mut R,
+    ) -> bool {
+        candidate <= self.level || candidate <= current
+    }
+}
+pub type GreatDeluge<P, N, R> = AcceptanceSearch<P, N, R, GreatDelugeAcceptance>;
+
+/// accepts neighbours at most deviation worse than the best solution found (the record)
+#[derive(Clone)]
+pub struct RecordToRecordAcceptance {
+    deviation: f64,
+}
+impl RecordToRecordAcceptance {
+    pub fn new(deviation: f64) -> Self {
+        debug_assert!(deviation >= 0.0);
+        Self { deviation }
+    }
+}
+impl Acceptance for RecordToRecordAcceptance {
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        best: f64,
+        _rng: &mut R,
+    ) -> bool {
+        candidate <= best + self.deviation || candidate <= current
+    }
+}
+pub type RecordToRecordTravel<P, N, R> = AcceptanceSearch<P, N, R, RecordToRecordAcceptance>;
+
+/// accepts neighbours at most the threshold worse than the current solution,
+/// the schedule gives the threshold at each iteration and the search stops once it is negative
+#[derive(Clone)]
+pub struct ThresholdAcceptance<TS: CoolingSchedule> {
+    schedule: TS,
+    threshold: f64,
+}
+impl<TS: CoolingSchedule> ThresholdAcceptance<TS> {
+    pub fn new(schedule: TS) -> Self {
+        Self {
+            schedule,
+            threshold: 0.0,
+        }
+    }
+}
+impl<TS: CoolingSchedule> Acceptance for ThresholdAcceptance<TS> {
+    fn next_iteration(&mut self, current: f64, progress: Option<f64>) -> bool {
+        if let Some(progress) = progress {
+            self.schedule.set_progress(progress);
+        }
+        self.threshold = self.schedule.temperature(current);
+        self.threshold >= 0.0
+    }
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        _best: f64,
+        _rng: &mut R,
+    ) -> bool {
+        let accepted = candidate - current <= self.threshold;
+        self.schedule.observe_acceptance(accepted);
+        accepted
+    }
+}
+pub type ThresholdAccepting<P, N, R, TS> = AcceptanceSearch<P, N, R, ThresholdAcceptance<TS>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::simulated_annealing::*;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn great_deluge() {
+        let mut rng = Splitmix64::from_u64(0);
+        let mut a = GreatDelugeAcceptance::new(10.0, 2.0);
+        // the level goes down from 10 to 8, then 6
+        assert!(a.next_iteration(5.0, None));
+        assert!(a.accept(5.0, 8.0, 0.0, &mut rng));
+        assert!(!a.accept(5.0, 9.0, 0.0, &mut rng));
+        assert!(a.next_iteration(5.0, None));
+        assert!(!a.accept(5.0, 7.0, 0.0, &mut rng));
+        assert!(a.accept(5.0, 6.0, 0.0, &mut rng));
+        // never worse than the current solution
+        assert!(a.accept(9.0, 9.0, 0.0, &mut rng));
+    }
+
+    #[test]
+    fn record_to_record() {
+        let mut rng = Splitmix64::from_u64(0);
+        let mut a = RecordToRecordAcceptance::new(2.0);
+        assert!(a.accept(6.0, 7.0, 5.0, &mut rng));
+        assert!(!a.accept(6.0, 7.5, 5.0, &mut rng));
+        assert!(a.accept(9.0, 9.0, 5.0, &mut rng));
+    }
+
+    #[test]
+    fn threshold() {
+        let mut rng = Splitmix64::from_u64(0);
+        let mut a = ThresholdAcceptance::new(ArithmeticGeometricCooling::new(4.0, 0.5, 0.0));
+        for threshold in [4.0, 2.0, 1.0] {
+            assert!(a.next_iteration(10.0, None));
+            assert!(a.accept(10.0, 10.0 + threshold, 0.0, &mut rng));
+            assert!(!a.accept(10.0, 10.5 + threshold, 0.0, &mut rng));
+        }
+        // stops once the threshold is negative
+        let mut a = ThresholdAcceptance::new(ArithmeticGeometricCooling::new(1.0, 1.0, -1.0));
+        assert!(a.next_iteration(10.0, None));
+        assert!(a.next_iteration(10.0, None));
+        assert!(!a.next_iteration(10.0, None));
+    }
+
+    #[test]
+    fn initial_report() {
+        let p = costs(&[1, 2]);
+        let initial = all_false(&p);
+        let deluge = GreatDeluge::new(
+            Flip,
+            initial.clone(),
+            GreatDelugeAcceptance::new(0.0, 0.0),
+            Splitmix64::from_u64(0),
+        );
+        assert_eq!(trace(deluge, &p, 0), vec![initial.clone()]);
+        let annealing = SimulatedAnnealing::new(
+            Flip,
+            initial,
+            Splitmix64::from_u64(0),
+            ArithmeticGeometricCooling::new(1.0, 1.0, 0.0),
+        );
+        assert_eq!(trace(annealing, &p, 0), vec![]);
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::tree_space::*;
+use crate::core::*;
+
+/// heuristic information of the best child relative to the worst one is (1 + EPS) / EPS
+const EPS: f64 = 0.1;
+
+/// key under which the pheromone of a decision is kept, e.g. the (last, next) pair of cities in
+/// a tour, so that decisions taken in different nodes can share their pheromone
+pub trait DecisionKey<P: Problem, TS: TreeIndirect<P>>: Clone {
+    type Key: Clone + Eq + Hash;
+    fn key(&self, ts: &TS, n: &TS::Node, cid: &TS::ChildId) -> Self::Key;
+}
+impl<P: Problem, TS: TreeIndirect<P>, K: Clone + Eq + Hash, F> DecisionKey<P, TS> for F
+where
+    F: Fn(&TS, &TS::Node, &TS::ChildId) -> K + Clone,
+{
+    type Key = K;
+    fn key(&self, ts: &TS, n: &TS::Node, cid: &TS::ChildId) -> K {
+        self(ts, n, cid)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PheromoneBounds {
+    /// Ant System
+    Unbounded,
+    /// MAX-MIN Ant System, the pheromone is kept in min..=max
+    MaxMin { min: f64, max: f64 },
+}
+
+#[derive(Clone, Debug)]
+pub struct AcoParameters {
+    /// ants per iteration
+    pub ants: usize,
+    /// exponent of the pheromone
+    pub alpha: f64,
+    /// exponent of the heuristic information
+    pub beta: f64,
+    /// fraction of the pheromone that evaporates at each iteration
+    pub evaporation: f64,
+    /// number of best ants of each iteration that deposit, the k-th best (from 0) deposits
+    /// (elite - k) / elite scaled by its quality, see AntColony
+    pub elite: usize,
+    pub initial_pheromone: f64,
+    pub bounds: PheromoneBounds,
+}
+impl Default for AcoParameters {
+    fn default() -> Self {
+        Self {
+            ants: 10,
+            alpha: 1.0,
+            beta: 2.0,
+            evaporation: 0.1,
+            elite: 1,
+            initial_pheromone: 1.0,
+            bounds: PheromoneBounds::MaxMin {
+                min: 0.01,
+                max: 10.0,
+            },
+        }
+    }
+}
+
+/// pheromone of each key, evaporated lazily
+struct Pheromone<K> {
+    values: HashMap<K, (f64, u64)>,
+    it: u64,
+    initial: f64,
+    retained: f64,
+    bounds: PheromoneBounds,
+}
+impl<K: Eq + Hash> Pheromone<K> {
+    fn clamp(&self, tau: f64) -> f64 {
+        match self.bounds {
+            PheromoneBounds::Unbounded => tau,
+            PheromoneBounds::MaxMin { min, max } => tau.clamp(min, max),
+        }
+    }
+    fn get(&self, k: &K) -> f64 {
+        let tau = match self.values.get(k) {
+            Some(&(tau, it)) => tau * self.retained.powi((self.it - it) as i32),
+            None => self.initial * self.retained.powi(self.it as i32),
+        };
+        self.clamp(tau)
+    }
+    fn deposit(&mut self, k: K, amount: f64) {
+        let tau = self.clamp(self.get(&k) + amount);
+        self.values.insert(k, (tau, self.it));
+    }
+    /// deposit of the elite best feasible ants, see AntColony, infeasible ants would give a NaN
+    /// quality when no solution is known
+    fn deposit_elite<O: Objective>(&mut self, mut ants: Vec<(O, Vec<K>)>, elite: usize, best: O) {
+        ants.retain(|a| a.0.is_feas());
+        ants.sort_by_key(|a| a.0);
+        let best: f64 = best.into();
+        for (rank, (obj, keys)) in ants.into_iter().take(elite).enumerate() {
+            let gap = (obj.into() - best).max(0.0);
+            let quality = (1.0 + best.abs()) / (1.0 + best.abs() + gap);
+            let amount = (elite - rank) as f64 / elite as f64 * quality;
+            for k in keys {
+                self.deposit(k, amount);
+            }
+        }
+    }
+}
+
+/// ant colony optimization: each ant walks from the root choosing each child with probability
+/// proportional to pheromone^alpha * heuristic^beta, where the heuristic of a child decreases
+/// from 1 / EPS to 1 / (1 + EPS) with its goodness between the best and the worst sibling.
+/// the pheromone evaporates at each iteration and the best feasible ants deposit on their decisions,
+/// scaled by the quality (1 + |best|) / (1 + |best| + obj - best) of their objective relative to
+/// the best one found so far, so that an ant matching it deposits the full amount
+#[derive(Clone)]
+pub struct AntColony<P: Problem, TS: TreeIndirectGuided<P>, K: DecisionKey<P, TS>, R: rng::Rng> {
+    key: K,
+    parameters: AcoParameters,
+    rng: R,
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirectGuided<P>, K: DecisionKey<P, TS>, R: rng::Rng>
+    AntColony<P, TS, K, R>
+{
+    pub fn new(key: K, parameters: AcoParameters, rng: R) -> Self {
+        debug_assert!(parameters.ants > 0 && parameters.initial_pheromone > 0.0);
+        debug_assert!((0.0..1.0).contains(&parameters.evaporation));
+        Self {
+            key,
+            parameters,
+            rng,
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+    /// walk of one ant, returns the keys of its decisions up to its best solution
+    fn walk<SK: SolutionKeeper<P>>(
+        &mut self,
+        ts: &TS,
+        pheromone: &Pheromone<K::Key>,
+        sk: &mut SK,
+    ) -> Option<(P::Obj, Vec<K::Key>)> {
+        let mut n = ts.root();
+        let mut keys = vec![];
+        let mut best: Option<(P::Obj, usize)> = None;
+        loop {
+            if let Some(obj) = ts.objective(&n) {
+                sk.add_solution_fn(|| ts.to_solution(&n).unwrap(), obj);
+                if best.is_none_or(|b| obj < b.0) {
+                    best = Some((obj, keys.len()));
+                }
+            }
+            let children: Vec<TS::ChildId> = ts.children_id(&n).collect();
+            if children.is_empty() {
+                break;
+            }
+            let goodness: Vec<f64> = children
+                .iter()
+                .map(|cid| ts.child_goodness(&n, cid).into())
+                .collect();
+            let min = goodness.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = goodness.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let child_keys: Vec<K::Key> = children
+                .iter()
+                .map(|cid| self.key.key(ts, &n, cid))
+                .collect();
+            let weights: Vec<f64> = goodness
+                .iter()
+                .zip(child_keys.iter())
+                .map(|(&g, k)| {
+                    let d = if max > min {
+                        (g - min) / (max - min)
+                    } else {
+                        0.0
+                    };
+                    let eta = 1.0 / (EPS + d);
+                    pheromone.get(k).powf(self.parameters.alpha) * eta.powf(self.parameters.beta)
+                })
+                .collect();
+            let total: f64 = weights.iter().sum();
+            let i = if total > 0.0 {
+                let mut x = self.rng.next01() * total;
+                weights
+                    .iter()
+                    .position(|&w| {
+                        x -= w;
+                        x < 0.0
+                    })
+                    .unwrap_or(children.len() - 1)
+            } else {
+                // all the pheromone evaporated
+                self.rng.next_u64() as usize % children.len()
+            };
+            n = ts.child(&n, &children[i]);
+            keys.push(child_keys[i].clone());
+        }
+        best.map(|(obj, len)| {
+            keys.truncate(len);
+            (obj, keys)
+        })
+    }
+}
+impl<P: Problem, TS: TreeIndirectGuided<P>, K: DecisionKey<P, TS>, R: rng::Rng> Solver<P>
+    for AntColony<P, TS, K, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let mut pheromone = Pheromone {
+            values: HashMap::new(),
+            it: 0,
+            initial: self.parameters.initial_pheromone,
+            retained: 1.0 - self.parameters.evaporation,
+            bounds: self.parameters.bounds,
+        };
+        'outer: loop {
+            let mut ants = Vec::with_capacity(self.parameters.ants);
+            for _ in 0..self.parameters.ants {
+                if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                    break 'outer;
+                }
+                sk.iter();
+                ants.extend(self.walk(&ts, &pheromone, sk));
+            }
+            pheromone.it += 1;
+            pheromone.deposit_elite(ants, self.parameters.elite, sk.best_obj());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lp::milp::MilpObj;
+
+    fn pheromone(bounds: PheromoneBounds) -> Pheromone<char> {
+        Pheromone {
+            values: HashMap::new(),
+            it: 0,
+            initial: 2.0,
+            retained: 0.9,
+            bounds,
+        }
+    }
+
+    #[test]
+    fn evaporation() {
+        let mut pheromone = pheromone(PheromoneBounds::Unbounded);
+        pheromone.it = 2;
+        pheromone.deposit('a', 1.0);
+        pheromone.it = 5;
+        assert!((pheromone.get(&'x') - 2.0 * 0.9f64.powi(5)).abs() < 1e-12);
+        let a = (2.0 * 0.9f64.powi(2) + 1.0) * 0.9f64.powi(3);
+        assert!((pheromone.get(&'a') - a).abs() < 1e-12);
+    }
+
+    #[test]
+    fn max_min_bounds() {
+        let mut pheromone = pheromone(PheromoneBounds::MaxMin { min: 0.5, max: 3.0 });
+        pheromone.it = 20;
+        assert_eq!(pheromone.get(&'x'), 0.5);
+        pheromone.deposit('a', 10.0);
+        assert_eq!(pheromone.get(&'a'), 3.0);
+        pheromone.it = 100;
+        assert_eq!(pheromone.get(&'a'), 0.5);
+    }
+
+    #[test]
+    fn elite_deposit() {
+        let mut pheromone = pheromone(PheromoneBounds::Unbounded);
+        let ants = vec![
+            (MilpObj(5.0), vec!['a']),
+            (MilpObj(3.0), vec!['b', 'd']),
+            (MilpObj(4.0), vec!['c']),
+        ];
+        pheromone.deposit_elite(ants, 2, MilpObj(3.0));
+        // the best ant deposits 1, the second 1/2 scaled by its quality 4 / 5
+        assert_eq!(pheromone.get(&'b'), 3.0);
+        assert_eq!(pheromone.get(&'d'), 3.0);
+        assert!((pheromone.get(&'c') - 2.4).abs() < 1e-12);
+        assert_eq!(pheromone.get(&'a'), 2.0);
+    }
+
+    #[test]
+    fn infeasible_ants() {
+        let mut pheromone = pheromone(PheromoneBounds::Unbounded);
+        // no solution is known yet
+        for _ in 0..3 {
+            pheromone.it += 1;
+            let ants = vec![(MilpObj::unfeas(), vec!['a']); 2];
+            pheromone.deposit_elite(ants, 2, MilpObj::unfeas());
+        }
+        assert!((pheromone.get(&'a') - 2.0 * 0.9f64.powi(3)).abs() < 1e-12);
+        pheromone.it += 1;
+        let ants = vec![(MilpObj(1.0), vec!['a']), (MilpObj::unfeas(), vec!['b'])];
+        pheromone.deposit_elite(ants, 2, MilpObj(1.0));
+        assert!((pheromone.get(&'a') - (2.0 * 0.9f64.powi(4) + 1.0)).abs() < 1e-12);
+        assert!((pheromone.get(&'b') - 2.0 * 0.9f64.powi(4)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,204 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::core::tree_space::*;
+use crate::core::*;
+use crate::solver::monte_carlo_tree_search::report;
+
+/// children of n whose dual bound is better than the incumbent, sorted by dual bound then
+/// primal bound
+fn children<P: Problem, TS: TreeIndirectBounded<P>>(
+    ts: &TS,
+    n: &TS::Node,
+    incumbent: P::Obj,
+) -> Vec<(P::Obj, P::Obj, TS::ChildId)> {
+    let mut children: Vec<_> = ts
+        .children_id(n)
+        .map(|cid| (ts.child_dual_bound(n, &cid, incumbent), cid))
+        .filter(|(dual, _)| *dual < incumbent)
+        .map(|(dual, cid)| (dual, ts.child_primal_bound(n, &cid), cid))
+        .collect();
+    children.sort_by_key(|c| (c.0, c.1));
+    children
+}
+
+/// depth first branch and bound: explores the best children (by dual bound) first and prunes
+/// the nodes whose dual bound is not better than the best solution. the global dual bound is
+/// reported at the start, when stopped and when the tree is exhausted, in which case the best
+/// solution is optimal
+#[derive(Clone)]
+pub struct DepthFirstBranchAndBound<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> {
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> DepthFirstBranchAndBound<P, TS> {
+    pub fn new() -> Self {
+        Self {
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> Default
+    for DepthFirstBranchAndBound<P, TS>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> Solver<P>
+    for DepthFirstBranchAndBound<P, TS>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let root = ts.root();
+        let root_dual = ts.dual_bound(&root, sk.best_obj());
+        sk.add_dual_bound(root_dual.min(sk.best_obj()));
+        let mut stack = vec![(root_dual, root)];
+        while let Some((dual, n)) = stack.pop() {
+            if dual >= sk.best_obj() {
+                continue;
+            }
+            if stop.stop(sk.best_obj(), root_dual) {
+                stack.push((dual, n));
+                let open = stack.iter().map(|s| s.0).min().unwrap();
+                sk.add_dual_bound(open.min(sk.best_obj()));
+                return;
+            }
+            sk.iter();
+            report(&ts, &n, sk);
+            for (dual, _, cid) in children(&ts, &n, sk.best_obj()).into_iter().rev() {
+                stack.push((dual, ts.child(&n, &cid)));
+            }
+        }
+        sk.add_dual_bound(sk.best_obj());
+    }
+}
+
+/// open node of BestFirstSearch, ordered by dual bound, then primal bound, then latest first
+struct Open<O, N> {
+    key: (O, O, Reverse<u64>),
+    node: N,
+}
+impl<O: Ord, N> PartialEq for Open<O, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+impl<O: Ord, N> Eq for Open<O, N> {}
+impl<O: Ord, N> PartialOrd for Open<O, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<O: Ord, N> Ord for Open<O, N> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// best first search: expands the open node with the best dual bound, which is the global
+/// dual bound and is reported every time it improves. the nodes whose dual bound is not better
+/// than the best solution are pruned, the best solution is optimal when no node is left open.
+/// the open nodes are kept in memory, which can grow quickly on large trees
+#[derive(Clone)]
+pub struct BestFirstSearch<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> {
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> BestFirstSearch<P, TS> {
+    pub fn new() -> Self {
+        Self {
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> Default for BestFirstSearch<P, TS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<P: Problem, TS: TreeIndirectBounded<P> + TreeBounded<P>> Solver<P> for BestFirstSearch<P, TS> {
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let root = ts.root();
+        let mut count = 0;
+        let mut open = BinaryHeap::new();
+        open.push(Reverse(Open {
+            key: (
+                ts.dual_bound(&root, sk.best_obj()),
+                ts.primal_bound(&root),
+                Reverse(count),
+            ),
+            node: root,
+        }));
+        let mut global_dual = P::Obj::unbounded();
+        while let Some(Reverse(Open { key, node: n })) = open.pop() {
+            let dual = key.0;
+            if dual >= sk.best_obj() {
+                // all the open nodes are pruned
+                break;
+            }
+            if dual > global_dual {
+                global_dual = dual;
+                sk.add_dual_bound(dual);
+            }
+            if stop.stop(sk.best_obj(), dual) {
+                return;
+            }
+            sk.iter();
+            report(&ts, &n, sk);
+            for (dual, primal, cid) in children(&ts, &n, sk.best_obj()) {
+                count += 1;
+                open.push(Reverse(Open {
+                    key: (dual, primal, Reverse(count)),
+                    node: ts.child(&n, &cid),
+                }));
+            }
+        }
+        sk.add_dual_bound(sk.best_obj());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn proves_optimality() {
+        for seed in 0..10 {
+            let p = problem(seed, 12);
+            let best = optimum(&p);
+            let sk = run(DepthFirstBranchAndBound::<_, Assign>::new(), &p, u64::MAX);
+            assert_eq!(sk.best_obj(), best);
+            assert_eq!(sk.dual_bound, sk.best_obj());
+            let sk = run(BestFirstSearch::<_, Assign>::new(), &p, u64::MAX);
+            assert_eq!(sk.best_obj(), best);
+            assert_eq!(sk.dual_bound, sk.best_obj());
+        }
+    }
+
+    #[test]
+    fn valid_dual_bound_when_stopped() {
+        for seed in 0..10 {
+            let p = problem(seed, 12);
+            let best = optimum(&p);
+            let sk = run(DepthFirstBranchAndBound::<_, Assign>::new(), &p, 20);
+            assert!(sk.dual_bound <= best);
+            let sk = run(BestFirstSearch::<_, Assign>::new(), &p, 20);
+            assert!(sk.dual_bound <= best);
+        }
+    }
+}
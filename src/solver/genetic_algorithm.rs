@@ -0,0 +1,368 @@
+use crate::core::*;
+use crate::solver::local_search::LocalSearch;
+
+/// child of two parents
+pub trait Crossover<P: Problem>: Clone {
+    fn crossover<R: rng::Rng>(&mut self, p: &P, a: &P::Sol, b: &P::Sol, rng: &mut R) -> P::Sol;
+}
+pub trait Mutation<P: Problem>: Clone {
+    fn mutate<R: rng::Rng>(&mut self, p: &P, solution: P::Sol, rng: &mut R) -> P::Sol;
+}
+/// distance between two solutions, used to keep the population diverse
+pub trait Distance<P: Problem>: Clone {
+    fn distance(&self, p: &P, a: &P::Sol, b: &P::Sol) -> f64;
+}
+impl<P: Problem, F: Fn(&P, &P::Sol, &P::Sol) -> f64 + Clone> Distance<P> for F {
+    fn distance(&self, p: &P, a: &P::Sol, b: &P::Sol) -> f64 {
+        self(p, a, b)
+    }
+}
+/// for replacement policies that do not need a distance
+#[derive(Clone)]
+pub struct NoDistance;
+impl<P: Problem> Distance<P> for NoDistance {
+    fn distance(&self, _p: &P, _a: &P::Sol, _b: &P::Sol) -> f64 {
+        0.0
+    }
+}
+/// local search that leaves the solution unchanged, for plain (non memetic) genetic algorithms
+#[derive(Clone)]
+pub struct NoLocalSearch;
+impl<P: Problem> LocalSearch<P> for NoLocalSearch {
+    fn improve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        _p: &P,
+        solution: (P::Sol, P::Obj),
+        _sk: &mut SK,
+        _stop: &mut S,
+    ) -> (P::Sol, P::Obj) {
+        solution
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    /// best of this many individuals drawn uniformly
+    Tournament(usize),
+    /// probability proportional to the gap with the worst objective of the population
+    Roulette,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GaMode {
+    /// one child per iteration, inserted by the replacement policy
+    SteadyState,
+    /// a full population of children per generation
+    Generational { elitism: usize },
+}
+
+/// how children enter the population
+#[derive(Clone)]
+pub enum Replacement<D> {
+    /// steady state: a child replaces the worst individual if it is not worse,
+    /// generational: the children and the elite individuals are the next population
+    Worst,
+    /// a child replaces the closest individual if it is not worse, so that the population keeps
+    /// distinct niches, generational mode applies it to each child of the generation
+    Crowding(D),
+}
+
+#[derive(Clone, Debug)]
+pub struct GaParameters {
+    pub selection: Selection,
+    pub mode: GaMode,
+    /// probability that a child is mutated
+    pub mutation_rate: f64,
+}
+impl Default for GaParameters {
+    fn default() -> Self {
+        Self {
+            selection: Selection::Tournament(2),
+            mode: GaMode::SteadyState,
+            mutation_rate: 0.2,
+        }
+    }
+}
+
+/// genetic algorithm, memetic if the children are improved by a local search
+#[derive(Clone)]
+pub struct GeneticAlgorithm<
+    P: Problem,
+    C: Crossover<P>,
+    M: Mutation<P>,
+    D: Distance<P>,
+    LS: LocalSearch<P>,
+    R: rng::Rng,
+> {
+    initial_population: Vec<(P::Sol, P::Obj)>,
+    crossover: C,
+    mutation: M,
+    parameters: GaParameters,
+    replacement: Replacement<D>,
+    local_search: LS,
+    rng: R,
+}
+impl<P: Problem, C: Crossover<P>, M: Mutation<P>, D: Distance<P>, R: rng::Rng>
+    GeneticAlgorithm<P, C, M, D, NoLocalSearch, R>
+{
+    pub fn new(
+        crossover: C,
+        mutation: M,
+        initial_population: Vec<(P::Sol, P::Obj)>,
+        parameters: GaParameters,
+        replacement: Replacement<D>,
+        rng: R,
+    ) -> Self {
+        Self::new_memetic(
+            crossover,
+            mutation,
+            NoLocalSearch,
+            initial_population,
+            parameters,
+            replacement,
+            rng,
+        )
+    }
+}
+impl<
+        P: Problem,
+        C: Crossover<P>,
+        M: Mutation<P>,
+        D: Distance<P>,
+        LS: LocalSearch<P>,
+        R: rng::Rng,
+    > GeneticAlgorithm<P, C, M, D, LS, R>
+{
+    /// each child is improved by the local search before entering the population
+    pub fn new_memetic(
+        crossover: C,
+        mutation: M,
+        local_search: LS,
+        initial_population: Vec<(P::Sol, P::Obj)>,
+        parameters: GaParameters,
+        replacement: Replacement<D>,
+        rng: R,
+    ) -> Self {
+        debug_assert!(initial_population.len() >= 2);
+        debug_assert!(!matches!(parameters.selection, Selection::Tournament(0)));
+        Self {
+            initial_population,
+            crossover,
+            mutation,
+            parameters,
+            replacement,
+            local_search,
+            rng,
+        }
+    }
+    fn select(&mut self, population: &[(P::Sol, P::Obj)]) -> usize {
+        let n = population.len();
+        match self.parameters.selection {
+            Selection::Tournament(k) => (0..k)
+                .map(|_| self.rng.next_u64() as usize % n)
+                .min_by_key(|&i| population[i].1)
+                .unwrap(),
+            Selection::Roulette => {
+                let objs: Vec<f64> = population.iter().map(|s| s.1.into()).collect();
+                let finite = || objs.iter().copied().filter(|o| o.is_finite());
+                let worst = finite().fold(f64::NEG_INFINITY, f64::max);
+                let best = finite().fold(f64::INFINITY, f64::min);
+                // the worst individuals keep a small chance
+                let base = ((worst - best) / n as f64).max(1e-9);
+                let weights: Vec<f64> = objs
+                    .iter()
+                    .map(|&o| if o.is_finite() { worst - o + base } else { 0.0 })
+                    .collect();
+                let mut x = self.rng.next01() * weights.iter().sum::<f64>();
+                weights
+                    .iter()
+                    .position(|&w| {
+                        x -= w;
+                        x < 0.0
+                    })
+                    .unwrap_or(n - 1)
+            }
+        }
+    }
+    fn child<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: &P,
+        population: &[(P::Sol, P::Obj)],
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> (P::Sol, P::Obj) {
+        let a = self.select(population);
+        let b = self.select(population);
+        let mut child =
+            self.crossover
+                .crossover(p, &population[a].0, &population[b].0, &mut self.rng);
+        if self.rng.next01() < self.parameters.mutation_rate {
+            child = self.mutation.mutate(p, child, &mut self.rng);
+        }
+        let obj = p.obj(&child);
+        sk.add_solution(&child, obj);
+        self.local_search.improve(p, (child, obj), sk, stop)
+    }
+    fn replace(&self, p: &P, population: &mut [(P::Sol, P::Obj)], child: (P::Sol, P::Obj)) {
+        let i = match &self.replacement {
+            Replacement::Worst => (0..population.len())
+                .max_by_key(|&i| population[i].1)
+                .unwrap(),
+            Replacement::Crowding(d) => {
+                population
+                    .iter()
+                    .map(|s| d.distance(p, &child.0, &s.0))
+                    .enumerate()
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .unwrap()
+                    .0
+            }
+        };
+        if child.1 <= population[i].1 {
+            population[i] = child;
+        }
+    }
+}
+impl<
+        P: Problem,
+        C: Crossover<P>,
+        M: Mutation<P>,
+        D: Distance<P>,
+        LS: LocalSearch<P>,
+        R: rng::Rng,
+    > Solver<P> for GeneticAlgorithm<P, C, M, D, LS, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let mut population = std::mem::take(&mut self.initial_population);
+        for (sol, obj) in population.iter() {
+            sk.add_solution(sol, *obj);
+        }
+        let mut children = Vec::with_capacity(population.len());
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let child = self.child(&p, &population, sk, &mut stop);
+            match self.parameters.mode {
+                GaMode::SteadyState => self.replace(&p, &mut population, child),
+                GaMode::Generational { elitism } => {
+                    children.push(child);
+                    let elitism = elitism.min(population.len());
+                    if matches!(self.replacement, Replacement::Worst)
+                        && children.len() + elitism >= population.len()
+                    {
+                        population.sort_by_key(|s| s.1);
+                        population.truncate(elitism);
+                        population.append(&mut children);
+                    } else if children.len() == population.len() {
+                        for child in std::mem::take(&mut children) {
+                            self.replace(&p, &mut population, child);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::maxsat::MaxSat;
+    use crate::solver::tests::*;
+
+    /// each value from either parent
+    #[derive(Clone)]
+    struct Uniform;
+    impl Crossover<MaxSat> for Uniform {
+        fn crossover<R: rng::Rng>(
+            &mut self,
+            _p: &MaxSat,
+            a: &Vec<bool>,
+            b: &Vec<bool>,
+            rng: &mut R,
+        ) -> Vec<bool> {
+            a.iter()
+                .zip(b)
+                .map(|(&x, &y)| {
+                    if rng.next_u64().is_multiple_of(2) {
+                        x
+                    } else {
+                        y
+                    }
+                })
+                .collect()
+        }
+    }
+    /// flips one random variable
+    #[derive(Clone)]
+    struct FlipOne;
+    impl Mutation<MaxSat> for FlipOne {
+        fn mutate<R: rng::Rng>(
+            &mut self,
+            p: &MaxSat,
+            mut sol: Vec<bool>,
+            rng: &mut R,
+        ) -> Vec<bool> {
+            let i = rng.next_u64() as usize % p.num_vars;
+            sol[i] = !sol[i];
+            sol
+        }
+    }
+
+    fn ga<D: Distance<MaxSat>>(
+        replacement: Replacement<D>,
+    ) -> GeneticAlgorithm<MaxSat, Uniform, FlipOne, D, NoLocalSearch, Splitmix64> {
+        let p = costs(&[1, 2, 4]);
+        let population = vec![all_false(&p); 2];
+        GeneticAlgorithm::new(
+            Uniform,
+            FlipOne,
+            population,
+            GaParameters::default(),
+            replacement,
+            Splitmix64::from_u64(0),
+        )
+    }
+
+    #[test]
+    fn crowding_replaces_nearest() {
+        let p = costs(&[1, 2, 4]);
+        let individual = |sol: [bool; 3]| (sol.to_vec(), p.obj(&sol.to_vec()));
+        let hamming = |_: &MaxSat, a: &Vec<bool>, b: &Vec<bool>| {
+            a.iter().zip(b).filter(|(x, y)| x != y).count() as f64
+        };
+        let initial = vec![
+            individual([true, true, true]),
+            individual([false, true, true]),
+            individual([true, false, false]),
+        ];
+        let mut population = initial.clone();
+        let crowding = ga(Replacement::Crowding(hamming));
+        // the nearest individual is the best one, not the worst
+        crowding.replace(&p, &mut population, individual([false, false, false]));
+        let expected = vec![
+            initial[0].clone(),
+            initial[1].clone(),
+            individual([false, false, false]),
+        ];
+        assert_eq!(population, expected);
+        // a child worse than its nearest individual is dropped
+        crowding.replace(&p, &mut population, individual([true, false, false]));
+        assert_eq!(population, expected);
+        let mut population = initial.clone();
+        ga(Replacement::<NoDistance>::Worst).replace(
+            &p,
+            &mut population,
+            individual([false, false, false]),
+        );
+        assert_eq!(population[0], individual([false, false, false]));
+        assert_eq!(population[1..], initial[1..]);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::core::neighbour_space::*;
+use crate::core::*;
+use crate::solver::local_search::LocalSearch;
+
+/// modification of a local optimum to escape its basin of attraction
+pub trait Perturbation<P: Problem>: Clone {
+    fn perturb<R: rng::Rng>(&mut self, p: &P, solution: P::Sol, rng: &mut R) -> (P::Sol, P::Obj);
+}
+
+/// makes `moves` random moves of the neighbourhood, the objective is then evaluated from scratch
+#[derive(Clone)]
+pub struct RandomMoves<N> {
+    ns: N,
+    moves: usize,
+}
+impl<N> RandomMoves<N> {
+    pub fn new(ns: N, moves: usize) -> Self {
+        Self { ns, moves }
+    }
+}
+impl<P: Problem, N: NeighbourhoodDirectRandom<P>> Perturbation<P> for RandomMoves<N> {
+    fn perturb<R: rng::Rng>(
+        &mut self,
+        p: &P,
+        mut solution: P::Sol,
+        rng: &mut R,
+    ) -> (P::Sol, P::Obj) {
+        for _ in 0..self.moves {
+            solution = self.ns.random_neighbour(p, &solution, rng);
+        }
+        let obj = p.obj(&solution);
+        (solution, obj)
+    }
+}
+
+/// which local optimum the next perturbation starts from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IlsAcceptance {
+    /// the new one if it is not worse than the current one
+    Better,
+    /// always the new one
+    RandomWalk,
+    /// as Better, but goes back to the initial solution after this many
+    /// iterations without improving the best solution
+    Restart(u64),
+}
+
+/// alternates a perturbation of the current local optimum and a local search from the
+/// perturbed solution until the stop condition fires
+#[derive(Clone)]
+pub struct IteratedLocalSearch<P: Problem, LS: LocalSearch<P>, PE: Perturbation<P>, R: rng::Rng> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    local_search: LS,
+    perturbation: PE,
+    acceptance: IlsAcceptance,
+    rng: R,
+}
+impl<P: Problem, LS: LocalSearch<P>, PE: Perturbation<P>, R: rng::Rng>
+    IteratedLocalSearch<P, LS, PE, R>
+{
+    pub fn new(
+        local_search: LS,
+        perturbation: PE,
+        initial_solution: (P::Sol, P::Obj),
+        acceptance: IlsAcceptance,
+        rng: R,
+    ) -> Self {
+        Self {
+            initial_solution: Some(initial_solution),
+            local_search,
+            perturbation,
+            acceptance,
+            rng,
+        }
+    }
+}
+impl<P: Problem, LS: LocalSearch<P>, PE: Perturbation<P>, R: rng::Rng> Solver<P>
+    for IteratedLocalSearch<P, LS, PE, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let initial_solution = self.initial_solution.take().unwrap();
+        sk.add_solution(&initial_solution.0, initial_solution.1);
+        let mut current = self
+            .local_search
+            .improve(&p, initial_solution.clone(), sk, &mut stop);
+        let mut since_best = 0;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let best_obj = sk.best_obj();
+            let (solution, obj) = self
+                .perturbation
+                .perturb(&p, current.0.clone(), &mut self.rng);
+            sk.add_solution(&solution, obj);
+            let candidate = self
+                .local_search
+                .improve(&p, (solution, obj), sk, &mut stop);
+            if sk.best_obj() < best_obj {
+                since_best = 0;
+            } else {
+                since_best += 1;
+            }
+            match self.acceptance {
+                IlsAcceptance::RandomWalk => current = candidate,
+                IlsAcceptance::Restart(patience) if since_best >= patience => {
+                    current = initial_solution.clone();
+                    since_best = 0;
+                }
+                IlsAcceptance::Better | IlsAcceptance::Restart(_) => {
+                    if candidate.1 <= current.1 {
+                        current = candidate;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::sat::cnf::SatObj;
+    use crate::sat::maxsat::MaxSat;
+    use crate::solver::tests::*;
+
+    /// leaves the solution as it is
+    #[derive(Clone)]
+    struct Unchanged;
+    impl LocalSearch<MaxSat> for Unchanged {
+        fn improve<SK: SolutionKeeper<MaxSat>, S: stop_condition::StopCondition<SatObj>>(
+            &mut self,
+            _p: &MaxSat,
+            solution: (Vec<bool>, SatObj),
+            _sk: &mut SK,
+            _stop: &mut S,
+        ) -> (Vec<bool>, SatObj) {
+            solution
+        }
+    }
+
+    /// records the solutions it perturbs and returns the scripted ones
+    #[derive(Clone)]
+    struct Scripted(Vec<Vec<bool>>, Rc<RefCell<Vec<Vec<bool>>>>);
+    impl Perturbation<MaxSat> for Scripted {
+        fn perturb<R: rng::Rng>(
+            &mut self,
+            p: &MaxSat,
+            solution: Vec<bool>,
+            _rng: &mut R,
+        ) -> (Vec<bool>, SatObj) {
+            let mut starts = self.1.borrow_mut();
+            let next = self.0[starts.len().min(self.0.len() - 1)].clone();
+            starts.push(solution);
+            let obj = p.obj(&next);
+            (next, obj)
+        }
+    }
+
+    #[test]
+    fn restart() {
+        let p = costs(&[1, 2, 4]);
+        let initial = vec![false, false, true];
+        // improves the best, then two worse solutions
+        let a = vec![false, true, false];
+        let script = vec![a.clone(), vec![true, true, false], vec![true, false, true]];
+        let starts = |acceptance| {
+            let perturbation = Scripted(script.clone(), Rc::default());
+            let recorded = perturbation.1.clone();
+            let initial = (initial.clone(), p.obj(&initial));
+            let solver = IteratedLocalSearch::new(
+                Unchanged,
+                perturbation,
+                initial,
+                acceptance,
+                Splitmix64::from_u64(0),
+            );
+            let sk = run(solver, &p, 4);
+            assert_eq!(sk.best_obj(), SatObj(2));
+            recorded.take()
+        };
+        let i = initial.clone();
+        assert_eq!(
+            starts(IlsAcceptance::Restart(2)),
+            [i.clone(), a.clone(), a.clone(), i.clone()]
+        );
+        assert_eq!(starts(IlsAcceptance::Better), [i, a.clone(), a.clone(), a]);
+    }
+}
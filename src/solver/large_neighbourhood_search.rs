@@ -0,0 +1,367 @@
+use crate::core::*;
+use crate::solver::acceptance::Acceptance;
+
+/// removes part of a solution, the partial solution is represented by the solution type
+pub trait Destroy<P: Problem>: Clone {
+    fn destroy<R: rng::Rng>(&mut self, p: &P, solution: P::Sol, rng: &mut R) -> P::Sol;
+}
+/// completes a partial solution made by a Destroy
+pub trait Repair<P: Problem>: Clone {
+    fn repair<R: rng::Rng>(&mut self, p: &P, partial: P::Sol, rng: &mut R) -> (P::Sol, P::Obj);
+}
+
+/// destroys and repairs the current solution at each iteration,
+/// the acceptance rule decides if the result replaces it
+#[derive(Clone)]
+pub struct LargeNeighbourhoodSearch<
+    P: Problem,
+    D: Destroy<P>,
+    RE: Repair<P>,
+    A: Acceptance,
+    R: rng::Rng,
+> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    destroy: D,
+    repair: RE,
+    acceptance: A,
+    rng: R,
+}
+impl<P: Problem, D: Destroy<P>, RE: Repair<P>, A: Acceptance, R: rng::Rng>
+    LargeNeighbourhoodSearch<P, D, RE, A, R>
+{
+    pub fn new(
+        destroy: D,
+        repair: RE,
+        initial_solution: (P::Sol, P::Obj),
+        acceptance: A,
+        rng: R,
+    ) -> Self {
+        Self {
+            initial_solution: Some(initial_solution),
+            destroy,
+            repair,
+            acceptance,
+            rng,
+        }
+    }
+}
+impl<P: Problem, D: Destroy<P>, RE: Repair<P>, A: Acceptance, R: rng::Rng> Solver<P>
+    for LargeNeighbourhoodSearch<P, D, RE, A, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
+        sk.add_solution(&current_solution, current_obj);
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            if !self
+                .acceptance
+                .next_iteration(current_obj.into(), stop.progress())
+            {
+                break;
+            }
+            let partial = self
+                .destroy
+                .destroy(&p, current_solution.clone(), &mut self.rng);
+            let (solution, obj) = self.repair.repair(&p, partial, &mut self.rng);
+            if self.acceptance.accept(
+                current_obj.into(),
+                obj.into(),
+                sk.best_obj().into(),
+                &mut self.rng,
+            ) {
+                current_solution = solution;
+                current_obj = obj;
+                sk.add_solution(&current_solution, current_obj);
+            }
+        }
+    }
+}
+
+/// scores and reaction factor of the adaptive weights
+#[derive(Clone, Debug)]
+pub struct AlnsParameters {
+    /// score when the result is a new best solution
+    pub best_score: f64,
+    /// score when the result is better than the current solution
+    pub better_score: f64,
+    /// score when the result is accepted without being better
+    pub accepted_score: f64,
+    /// weight given to the last segment when updating the weights
+    pub reaction: f64,
+    /// number of iterations between two updates of the weights
+    pub segment_length: u64,
+}
+impl Default for AlnsParameters {
+    fn default() -> Self {
+        Self {
+            best_score: 33.0,
+            better_score: 9.0,
+            accepted_score: 13.0,
+            reaction: 0.1,
+            segment_length: 100,
+        }
+    }
+}
+
+/// weight, score and number of uses in the current segment of each pair of operators
+#[derive(Clone)]
+struct OperatorWeights {
+    weights: Vec<f64>,
+    scores: Vec<f64>,
+    uses: Vec<u64>,
+}
+impl OperatorWeights {
+    fn new(pairs: usize) -> Self {
+        Self {
+            weights: vec![1.0; pairs],
+            scores: vec![0.0; pairs],
+            uses: vec![0; pairs],
+        }
+    }
+    /// roulette wheel on the weights
+    fn choose<R: rng::Rng>(&self, rng: &mut R) -> usize {
+        let mut x = rng.next01() * self.weights.iter().sum::<f64>();
+        self.weights
+            .iter()
+            .position(|&w| {
+                x -= w;
+                x < 0.0
+            })
+            .unwrap_or(self.weights.len() - 1)
+    }
+    fn record(&mut self, pair: usize, score: f64) {
+        self.uses[pair] += 1;
+        self.scores[pair] += score;
+    }
+    /// moves the weights of the pairs used in the segment towards their average score
+    fn end_segment(&mut self, reaction: f64) {
+        for i in 0..self.weights.len() {
+            if self.uses[i] > 0 {
+                self.weights[i] = (1.0 - reaction) * self.weights[i]
+                    + reaction * self.scores[i] / self.uses[i] as f64;
+            }
+        }
+        // keeps every pair reachable
+        let max = self.weights.iter().copied().fold(0.0, f64::max);
+        for w in self.weights.iter_mut() {
+            *w = w.max(max * 1e-3).max(f64::MIN_POSITIVE);
+        }
+        self.scores.fill(0.0);
+        self.uses.fill(0);
+    }
+}
+
+/// large neighbourhood search choosing the destroy and repair operators by roulette wheel on a
+/// weight per pair of operators, each segment the weights move towards the average score the
+/// pair obtained. different operator types can be given as variants of an enum
+#[derive(Clone)]
+pub struct AdaptiveLargeNeighbourhoodSearch<
+    P: Problem,
+    D: Destroy<P>,
+    RE: Repair<P>,
+    A: Acceptance,
+    R: rng::Rng,
+> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    destroys: Vec<D>,
+    repairs: Vec<RE>,
+    parameters: AlnsParameters,
+    acceptance: A,
+    rng: R,
+}
+impl<P: Problem, D: Destroy<P>, RE: Repair<P>, A: Acceptance, R: rng::Rng>
+    AdaptiveLargeNeighbourhoodSearch<P, D, RE, A, R>
+{
+    pub fn new(
+        destroys: Vec<D>,
+        repairs: Vec<RE>,
+        initial_solution: (P::Sol, P::Obj),
+        parameters: AlnsParameters,
+        acceptance: A,
+        rng: R,
+    ) -> Self {
+        debug_assert!(!destroys.is_empty() && !repairs.is_empty());
+        debug_assert!(parameters.segment_length > 0);
+        Self {
+            initial_solution: Some(initial_solution),
+            destroys,
+            repairs,
+            parameters,
+            acceptance,
+            rng,
+        }
+    }
+}
+impl<P: Problem, D: Destroy<P>, RE: Repair<P>, A: Acceptance, R: rng::Rng> Solver<P>
+    for AdaptiveLargeNeighbourhoodSearch<P, D, RE, A, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
+        sk.add_solution(&current_solution, current_obj);
+        let nr = self.repairs.len();
+        let mut weights = OperatorWeights::new(self.destroys.len() * nr);
+        let mut it = 0u64;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            if !self
+                .acceptance
+                .next_iteration(current_obj.into(), stop.progress())
+            {
+                break;
+            }
+            let pair = weights.choose(&mut self.rng);
+            let partial =
+                self.destroys[pair / nr].destroy(&p, current_solution.clone(), &mut self.rng);
+            let (solution, obj) = self.repairs[pair % nr].repair(&p, partial, &mut self.rng);
+            let best_obj = sk.best_obj();
+            let mut score = 0.0;
+            if self.acceptance.accept(
+                current_obj.into(),
+                obj.into(),
+                best_obj.into(),
+                &mut self.rng,
+            ) {
+                score = if obj < best_obj {
+                    self.parameters.best_score
+                } else if obj < current_obj {
+                    self.parameters.better_score
+                } else {
+                    self.parameters.accepted_score
+                };
+                current_solution = solution;
+                current_obj = obj;
+                sk.add_solution(&current_solution, current_obj);
+            }
+            weights.record(pair, score);
+            it += 1;
+            if it.is_multiple_of(self.parameters.segment_length) {
+                weights.end_segment(self.parameters.reaction);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::cnf::SatObj;
+    use crate::sat::maxsat::MaxSat;
+    use crate::solver::acceptance::RecordToRecordAcceptance;
+    use crate::solver::tests::*;
+
+    /// flips the scripted variables in turn
+    #[derive(Clone)]
+    struct Flips(Vec<usize>);
+    impl Destroy<MaxSat> for Flips {
+        fn destroy<R: rng::Rng>(
+            &mut self,
+            _p: &MaxSat,
+            mut sol: Vec<bool>,
+            _rng: &mut R,
+        ) -> Vec<bool> {
+            let i = self.0.remove(0);
+            sol[i] = !sol[i];
+            sol
+        }
+    }
+    /// keeps the solution as it is
+    #[derive(Clone)]
+    struct Evaluate;
+    impl Repair<MaxSat> for Evaluate {
+        fn repair<R: rng::Rng>(
+            &mut self,
+            p: &MaxSat,
+            sol: Vec<bool>,
+            _rng: &mut R,
+        ) -> (Vec<bool>, SatObj) {
+            let obj = p.obj(&sol);
+            (sol, obj)
+        }
+    }
+
+    #[test]
+    fn rejected_repairs_are_dropped() {
+        let p = costs(&[1, 2, 4]);
+        let initial = (vec![true; 3], SatObj(7));
+        // setting x2 back is rejected so the last flip starts from x2 unset
+        let solver = LargeNeighbourhoodSearch::new(
+            Flips(vec![2, 2, 1]),
+            Evaluate,
+            initial,
+            RecordToRecordAcceptance::new(0.0),
+            Splitmix64::from_u64(0),
+        );
+        let solutions = trace(solver, &p, 3);
+        assert_eq!(flips(&solutions), [2, 1]);
+        assert_eq!(solutions.last().unwrap().1, SatObj(1));
+    }
+
+    #[test]
+    fn successful_weights_grow() {
+        let mut weights = OperatorWeights::new(3);
+        for _ in 0..10 {
+            weights.record(0, 33.0);
+            weights.record(1, 0.0);
+        }
+        weights.end_segment(0.1);
+        assert!((weights.weights[0] - (0.9 + 3.3)).abs() < 1e-9);
+        assert!((weights.weights[1] - 0.9).abs() < 1e-9);
+        // unused in the segment
+        assert_eq!(weights.weights[2], 1.0);
+        // the pair is chosen in proportion to its weight
+        let mut rng = Splitmix64::from_u64(0);
+        let chosen = (0..10000).filter(|_| weights.choose(&mut rng) == 0).count();
+        assert!((chosen as f64 / 10000.0 - 4.2 / 6.1).abs() < 0.02);
+    }
+
+    #[test]
+    fn adaptive_weights_favour_improving_destroy() {
+        // destroy 0 always unsets a set variable and improves, destroy 1 sets it back
+        #[derive(Clone)]
+        struct Toggle(bool);
+        impl Destroy<MaxSat> for Toggle {
+            fn destroy<R: rng::Rng>(
+                &mut self,
+                _p: &MaxSat,
+                mut sol: Vec<bool>,
+                _rng: &mut R,
+            ) -> Vec<bool> {
+                if let Some(x) = sol.iter_mut().find(|x| **x != self.0) {
+                    *x = self.0;
+                }
+                sol
+            }
+        }
+        let p = costs(&[1; 200]);
+        let solver = AdaptiveLargeNeighbourhoodSearch::new(
+            vec![Toggle(false), Toggle(true)],
+            vec![Evaluate],
+            (vec![true; 200], SatObj(200)),
+            AlnsParameters {
+                segment_length: 10,
+                ..AlnsParameters::default()
+            },
+            RecordToRecordAcceptance::new(0.0),
+            Splitmix64::from_u64(0),
+        );
+        // only the improvements are accepted, half of the iterations with fixed weights
+        let accepted = trace(solver, &p, 100).len() - 1;
+        assert!(accepted > 75, "{}", accepted);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::core::neighbour_space::*;
+use crate::core::*;
+
+/// late acceptance hill climbing (Burke & Bykov): a neighbour is accepted if it is not worse
+/// than the current solution or than the current solution history_length iterations ago
+#[derive(Clone)]
+pub struct LateAcceptanceHillClimbing<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    history_length: usize,
+    rng: R,
+    ns: N,
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>
+    LateAcceptanceHillClimbing<P, N, R>
+{
+    pub fn new(ns: N, initial_solution: (P::Sol, P::Obj), history_length: usize, rng: R) -> Self {
+        debug_assert!(history_length > 0);
+        Self {
+            initial_solution: Some(initial_solution),
+            history_length,
+            rng,
+            ns,
+        }
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
+    for LateAcceptanceHillClimbing<P, N, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
+        sk.add_solution(&current_solution, current_obj);
+        let mut history = vec![current_obj; self.history_length];
+        let mut v = 0;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let nid = self
+                .ns
+                .random_neighbour_id(&p, &current_solution, &mut self.rng);
+            let nobj = self.ns.neighbour_obj(&p, &current_solution, &nid);
+            if nobj <= history[v] || nobj <= current_obj {
+                current_solution = self.ns.random_neighbour(&p, current_solution, nid);
+                current_obj = nobj;
+                sk.add_solution(&current_solution, current_obj);
+            }
+            history[v] = current_obj;
+            v = (v + 1) % self.history_length;
+        }
+    }
+}
+
+/// which iterations count as steps for StepCountingHillClimbing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepCounting {
+    All,
+    /// only the iterations where the neighbour is accepted
+    Accepted,
+    /// only the iterations where the neighbour is strictly better than the current solution
+    Improving,
+}
+
+/// step counting hill climbing (Bykov & Petrovic): a neighbour is accepted if it is not worse
+/// than the current solution or better than the bound, which is set to the current objective
+/// every history_length steps
+#[derive(Clone)]
+pub struct StepCountingHillClimbing<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    history_length: usize,
+    counting: StepCounting,
+    rng: R,
+    ns: N,
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> StepCountingHillClimbing<P, N, R> {
+    pub fn new(
+        ns: N,
+        initial_solution: (P::Sol, P::Obj),
+        history_length: usize,
+        counting: StepCounting,
+        rng: R,
+    ) -> Self {
+        debug_assert!(history_length > 0);
+        Self {
+            initial_solution: Some(initial_solution),
+            history_length,
+            counting,
+            rng,
+            ns,
+        }
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
+    for StepCountingHillClimbing<P, N, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
+        sk.add_solution(&current_solution, current_obj);
+        let mut bound = current_obj;
+        let mut steps = 0;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let nid = self
+                .ns
+                .random_neighbour_id(&p, &current_solution, &mut self.rng);
+            let nobj = self.ns.neighbour_obj(&p, &current_solution, &nid);
+            let improving = nobj < current_obj;
+            let accepted = nobj < bound || nobj <= current_obj;
+            if accepted {
+                current_solution = self.ns.random_neighbour(&p, current_solution, nid);
+                current_obj = nobj;
+                sk.add_solution(&current_solution, current_obj);
+            }
+            steps += match self.counting {
+                StepCounting::All => 1,
+                StepCounting::Accepted => accepted as usize,
+                StepCounting::Improving => improving as usize,
+            };
+            if steps >= self.history_length {
+                bound = current_obj;
+                steps = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn late_acceptance_history() {
+        // from 7, x2 and x1 are unset (3, then 1), setting x1 again gives 3 which is worse than
+        // the current 1 but not than 3 two steps back, setting x2 gives 7 which is rejected
+        let p = costs(&[1, 2, 4]);
+        let run = |history_length, moves: &[usize]| {
+            let initial = vec![true; 3];
+            let obj = p.obj(&initial);
+            let search = LateAcceptanceHillClimbing::new(
+                Script::new(moves),
+                (initial, obj),
+                history_length,
+                Splitmix64::from_u64(0),
+            );
+            trace(search, &p, moves.len() as u64)
+        };
+        let solutions = run(2, &[2, 1, 1, 2]);
+        assert_eq!(flips(&solutions), vec![2, 1, 1]);
+        let objs: Vec<i64> = solutions.iter().map(|s| s.1 .0).collect();
+        assert_eq!(objs, vec![7, 3, 1, 3]);
+        // one step back is the current solution
+        assert_eq!(flips(&run(1, &[2, 1, 1])), vec![2, 1]);
+    }
+
+    #[test]
+    fn step_counting() {
+        // from 7 with a bound updated every 2 steps: rejected moves only count with All and
+        // accepted worsening ones only with Accepted, so the bound is 0, 1 or 4 when x0 is set
+        // again (from 0 to 1), and setting x1 (from 1 to 3) is only accepted below 4
+        let p = costs(&[1, 2, 4]);
+        let moves = [0, 0, 1, 2, 0, 1];
+        let run = |counting| {
+            let initial = vec![true; 3];
+            let obj = p.obj(&initial);
+            let search = StepCountingHillClimbing::new(
+                Script::new(&moves),
+                (initial, obj),
+                2,
+                counting,
+                Splitmix64::from_u64(0),
+            );
+            flips(&trace(search, &p, moves.len() as u64))
+        };
+        assert_eq!(run(StepCounting::All), vec![0, 1, 2]);
+        assert_eq!(run(StepCounting::Accepted), vec![0, 1, 2, 0]);
+        assert_eq!(run(StepCounting::Improving), vec![0, 1, 2, 0, 1]);
+    }
+}
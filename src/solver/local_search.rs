@@ -1,6 +1,23 @@
 use crate::core::neighbour_space::*;
 use crate::core::*;
 
+/// consecutive non improving samples after which FirstImprovingRandomLocalSearch::improve
+/// takes the solution as a local optimum, unless set with with_patience
+const FIRST_IMPROVING_PATIENCE: u64 = 1000;
+
+/// local search that can be restarted from any solution, as used by IteratedLocalSearch
+pub trait LocalSearch<P: Problem>: Clone {
+    /// improves the solution until a local optimum is reached or the stop condition fires,
+    /// reporting the solutions found to sk, and returns the final one
+    fn improve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: &P,
+        solution: (P::Sol, P::Obj),
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> (P::Sol, P::Obj);
+}
+
 #[derive(Clone)]
 pub struct FirstImprovingRandomLocalSearch<
     P: Problem,
@@ -10,15 +27,22 @@ pub struct FirstImprovingRandomLocalSearch<
     initial_solution: Option<(P::Sol, P::Obj)>,
     rng: R,
     ns: N,
+    patience: u64,
 }
 impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>
     FirstImprovingRandomLocalSearch<P, N, R>
 {
     pub fn new(ns: N, initial_solution: (P::Sol, P::Obj), rng: R) -> Self {
+        Self::with_patience(ns, initial_solution, rng, FIRST_IMPROVING_PATIENCE)
+    }
+    /// as a LocalSearch, improve returns after `patience` consecutive random neighbours that
+    /// do not improve the solution, the solver itself runs until the stop condition fires
+    pub fn with_patience(ns: N, initial_solution: (P::Sol, P::Obj), rng: R, patience: u64) -> Self {
         Self {
             initial_solution: Some(initial_solution),
             rng,
             ns,
+            patience,
         }
     }
 }
@@ -47,6 +71,36 @@ impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
         }
     }
 }
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> LocalSearch<P>
+    for FirstImprovingRandomLocalSearch<P, N, R>
+{
+    fn improve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: &P,
+        (mut solution, mut obj): (P::Sol, P::Obj),
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> (P::Sol, P::Obj) {
+        let mut failures = 0;
+        while failures < self.patience {
+            if stop.stop(obj, P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let nid = self.ns.random_neighbour_id(p, &solution, &mut self.rng);
+            let nobj = self.ns.neighbour_obj(p, &solution, &nid);
+            if nobj < obj {
+                solution = self.ns.random_neighbour(p, solution, nid);
+                obj = nobj;
+                sk.add_solution(&solution, obj);
+                failures = 0;
+            } else {
+                failures += 1;
+            }
+        }
+        (solution, obj)
+    }
+}
 
 #[derive(Clone)]
 pub struct SteepestDescentLocalSearch<P: Problem, N: NeighbourhoodIndirect<P>> {
@@ -68,8 +122,19 @@ impl<P: Problem, N: NeighbourhoodIndirect<P>> Solver<P> for SteepestDescentLocal
         sk: &mut SK,
         mut stop: S,
     ) {
-        let (mut solution, mut obj) = self.initial_solution.take().unwrap();
-        sk.add_solution(&solution, obj);
+        let initial_solution = self.initial_solution.take().unwrap();
+        sk.add_solution(&initial_solution.0, initial_solution.1);
+        self.improve(&p, initial_solution, sk, &mut stop);
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirect<P>> LocalSearch<P> for SteepestDescentLocalSearch<P, N> {
+    fn improve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: &P,
+        (mut solution, mut obj): (P::Sol, P::Obj),
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> (P::Sol, P::Obj) {
         loop {
             if stop.stop(obj, P::Obj::unbounded()) {
                 break;
@@ -77,20 +142,21 @@ impl<P: Problem, N: NeighbourhoodIndirect<P>> Solver<P> for SteepestDescentLocal
             sk.iter();
             let mut best_nid = None;
             let mut best_nobj = obj;
-            for nid in self.ns.neighbourhood_id(&p, &solution) {
-                let nobj = self.ns.neighbour_obj(&p, &solution, &nid);
+            for nid in self.ns.neighbourhood_id(p, &solution) {
+                let nobj = self.ns.neighbour_obj(p, &solution, &nid);
                 if nobj < best_nobj {
                     best_nobj = nobj;
                     best_nid = Some(nid);
                 }
             }
             if let Some(nid) = best_nid {
-                solution = self.ns.neighbour(&p, solution, nid);
+                solution = self.ns.neighbour(p, solution, nid);
                 obj = best_nobj;
                 sk.add_solution(&solution, obj);
             } else {
                 break;
             }
         }
+        (solution, obj)
     }
 }
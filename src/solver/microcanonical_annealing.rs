@@ -1,13 +1,43 @@
 use crate::core::neighbour_space::*;
 use crate::core::*;
+use crate::solver::acceptance::*;
 
+/// accepts a neighbour if the demon has enough energy to pay for the increase of the objective,
+/// the energy given by improving moves goes to the demon
 #[derive(Clone)]
-pub struct MicrocanonicalAnnealing<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> {
-    initial_solution: Option<(P::Sol, P::Obj)>,
-    initial_demon_energy: f64,
-    rng: R,
-    ns: N,
+pub struct DemonAcceptance {
+    energy: f64,
 }
+impl DemonAcceptance {
+    pub fn new(initial_energy: f64) -> Self {
+        debug_assert!(initial_energy >= 0.0);
+        Self {
+            energy: initial_energy,
+        }
+    }
+}
+impl Acceptance for DemonAcceptance {
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        _best: f64,
+        _rng: &mut R,
+    ) -> bool {
+        let delta = candidate - current;
+        if self.energy >= delta {
+            self.energy -= delta;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MicrocanonicalAnnealing<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>(
+    AcceptanceSearch<P, N, R, DemonAcceptance>,
+);
 impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> MicrocanonicalAnnealing<P, N, R> {
     pub fn new(
         ns: N,
@@ -15,13 +45,12 @@ impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> MicrocanonicalA
         initial_demon_energy: f64,
         rng: R,
     ) -> Self {
-        debug_assert!(initial_demon_energy >= 0.0);
-        Self {
-            initial_solution: Some(initial_solution),
-            initial_demon_energy,
-            rng,
+        Self(AcceptanceSearch::new(
             ns,
-        }
+            initial_solution,
+            DemonAcceptance::new(initial_demon_energy),
+            rng,
+        ))
     }
 }
 impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
@@ -31,27 +60,8 @@ impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
         &mut self,
         p: P,
         sk: &mut SK,
-        mut stop: S,
+        stop: S,
     ) {
-        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
-        sk.add_solution(&current_solution, current_obj);
-        let mut demon_energy = self.initial_demon_energy;
-        loop {
-            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
-                break;
-            }
-            sk.iter();
-            let nid = self
-                .ns
-                .random_neighbour_id(&p, &current_solution, &mut self.rng);
-            let nobj = self.ns.neighbour_obj(&p, &current_solution, &nid);
-            let delta = nobj.into() - current_obj.into();
-            if demon_energy >= delta {
-                current_solution = self.ns.random_neighbour(&p, current_solution, nid);
-                current_obj = nobj;
-                demon_energy -= delta;
-                sk.add_solution(&current_solution, current_obj);
-            }
-        }
+        self.0.solve(p, sk, stop)
     }
 }
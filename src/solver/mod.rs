@@ -1,4 +1,18 @@
+pub mod acceptance;
+pub mod ant_colony;
 pub mod beam_search;
+pub mod branch_and_bound;
+pub mod genetic_algorithm;
+pub mod iterated_local_search;
+pub mod large_neighbourhood_search;
+pub mod late_acceptance;
 pub mod local_search;
 pub mod microcanonical_annealing;
+pub mod monte_carlo_tree_search;
+pub mod nested_monte_carlo;
+pub mod parallel_tempering;
 pub mod simulated_annealing;
+pub mod tabu_search;
+#[cfg(test)]
+mod tests;
+pub mod variable_neighbourhood_search;
@@ -0,0 +1,361 @@
+use crate::core::tree_space::*;
+use crate::core::*;
+
+/// reports the solution represented by the node, if any, and returns its objective
+pub(crate) fn report<P: Problem, TS: Tree<P>, SK: SolutionKeeper<P>>(
+    ts: &TS,
+    n: &TS::Node,
+    sk: &mut SK,
+) -> P::Obj {
+    match ts.objective(n) {
+        Some(obj) => {
+            sk.add_solution_fn(|| ts.to_solution(n).unwrap(), obj);
+            obj
+        }
+        None => P::Obj::unfeas(),
+    }
+}
+
+/// random descent from a node to a leaf
+pub trait Rollout<P: Problem, TS: Tree<P>>: Clone {
+    /// reports every solution met on the way (including n) and returns n, unless the rollout
+    /// consumed it, along with the best of their objectives, unfeas if there is none
+    fn rollout<SK: SolutionKeeper<P>, R: rng::Rng>(
+        &mut self,
+        ts: &TS,
+        n: TS::Node,
+        sk: &mut SK,
+        rng: &mut R,
+    ) -> (Option<TS::Node>, P::Obj);
+}
+
+/// rollout with TreeDirectRandom::random_child
+#[derive(Clone)]
+pub struct RandomRollout;
+impl<P: Problem, TS: TreeDirectRandom<P>> Rollout<P, TS> for RandomRollout {
+    fn rollout<SK: SolutionKeeper<P>, R: rng::Rng>(
+        &mut self,
+        ts: &TS,
+        n: TS::Node,
+        sk: &mut SK,
+        rng: &mut R,
+    ) -> (Option<TS::Node>, P::Obj) {
+        let mut best = report(ts, &n, sk);
+        let mut current = ts.random_child(&n, rng);
+        while let Some(c) = current {
+            best = best.min(report(ts, &c, sk));
+            current = ts.random_child(&c, rng);
+        }
+        (Some(n), best)
+    }
+}
+
+/// rollout with TreeRollbackDirectRandom::random_child_rollback that rolls back to the starting
+/// node instead of cloning it, the leaves are detected with TreeIndirect::children_id since
+/// random_child_rollback consumes the node. if it returns None anyway the node is taken as a
+/// leaf and the starting node is lost
+#[derive(Clone)]
+pub struct RollbackRollout;
+impl<P: Problem, TS: TreeRollbackDirectRandom<P> + TreeIndirect<P>> Rollout<P, TS>
+    for RollbackRollout
+{
+    fn rollout<SK: SolutionKeeper<P>, R: rng::Rng>(
+        &mut self,
+        ts: &TS,
+        mut n: TS::Node,
+        sk: &mut SK,
+        rng: &mut R,
+    ) -> (Option<TS::Node>, P::Obj) {
+        let mut best = report(ts, &n, sk);
+        let mut infos = vec![];
+        while ts.children_id(&n).next().is_some() {
+            match ts.random_child_rollback(n, rng) {
+                Some((c, info)) => {
+                    n = c;
+                    infos.push(info);
+                }
+                None => return (None, best),
+            }
+            best = best.min(report(ts, &n, sk));
+        }
+        while let Some(info) = infos.pop() {
+            n = ts.rollback(n, info);
+        }
+        (Some(n), best)
+    }
+}
+
+/// node of the search tree of MonteCarloTreeSearch
+struct Entry<P: Problem, TS: TreeIndirect<P>> {
+    node: TS::Node,
+    untried: Vec<TS::ChildId>,
+    children: Vec<usize>,
+    visits: u64,
+    /// number and sum of the objectives of the feasible rollouts through this node
+    feasible: u64,
+    sum: f64,
+    /// the whole subtree has been expanded
+    exhausted: bool,
+}
+impl<P: Problem, TS: TreeIndirect<P>> Entry<P, TS> {
+    /// value + exploration * sqrt(ln(parent visits) / visits), see MonteCarloTreeSearch
+    fn uct(&self, ln_parent_visits: f64, worst: f64, best: f64, exploration: f64) -> f64 {
+        let visits = self.visits as f64;
+        let value = if worst > best {
+            (self.feasible as f64 * worst - self.sum) / (worst - best) / visits
+        } else {
+            self.feasible as f64 / visits
+        };
+        value + exploration * (ln_parent_visits / visits).sqrt()
+    }
+}
+
+/// monte carlo tree search with UCT: descends the search tree choosing the child maximizing
+/// value + exploration * sqrt(ln(parent visits) / visits), where value is the mean of the
+/// rollout objectives through the child normalized in 0..=1 between the worst and the best ones
+/// seen so far (0 for unfeasible rollouts), expands one child, runs a rollout from it and
+/// backpropagates its objective. fully expanded subtrees are not visited again, so small trees
+/// are enumerated and the best solution is proven optimal
+#[derive(Clone)]
+pub struct MonteCarloTreeSearch<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng> {
+    exploration: f64,
+    rollout: RO,
+    rng: R,
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng>
+    MonteCarloTreeSearch<P, TS, RO, R>
+{
+    pub fn new(exploration: f64, rollout: RO, rng: R) -> Self {
+        Self {
+            exploration,
+            rollout,
+            rng,
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+    fn entry(ts: &TS, node: TS::Node) -> Entry<P, TS> {
+        let untried: Vec<TS::ChildId> = ts.children_id(&node).collect();
+        Entry {
+            node,
+            exhausted: untried.is_empty(),
+            untried,
+            children: vec![],
+            visits: 0,
+            feasible: 0,
+            sum: 0.0,
+        }
+    }
+}
+impl<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng> Solver<P>
+    for MonteCarloTreeSearch<P, TS, RO, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let root = ts.root();
+        report(&ts, &root, sk);
+        let mut tree = vec![Self::entry(&ts, root)];
+        // worst and best feasible rollout objectives
+        let mut worst = f64::NEG_INFINITY;
+        let mut best = f64::INFINITY;
+        loop {
+            if tree[0].exhausted {
+                sk.add_dual_bound(sk.best_obj());
+                break;
+            }
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            // selection
+            let mut path = vec![0];
+            let mut i = 0;
+            while tree[i].untried.is_empty() {
+                let ln_visits = (tree[i].visits as f64).ln();
+                i = *tree[i]
+                    .children
+                    .iter()
+                    .filter(|&&c| !tree[c].exhausted)
+                    .max_by(|&&a, &&b| {
+                        let uct = |c: usize| tree[c].uct(ln_visits, worst, best, self.exploration);
+                        uct(a).total_cmp(&uct(b))
+                    })
+                    .unwrap();
+                path.push(i);
+            }
+            // expansion and rollout
+            let k = self.rng.next_u64() as usize % tree[i].untried.len();
+            let cid = tree[i].untried.swap_remove(k);
+            let child = ts.child(&tree[i].node, &cid);
+            let (child, obj) = self.rollout.rollout(&ts, child, sk, &mut self.rng);
+            let child = child.unwrap_or_else(|| ts.child(&tree[i].node, &cid));
+            let c = tree.len();
+            tree[i].children.push(c);
+            path.push(c);
+            tree.push(Self::entry(&ts, child));
+            // backpropagation
+            let feasible = obj.is_feas();
+            if feasible {
+                worst = worst.max(obj.into());
+                best = best.min(obj.into());
+            }
+            for &i in path.iter().rev() {
+                let e = &tree[i];
+                let exhausted = e.exhausted
+                    || e.untried.is_empty() && e.children.iter().all(|&c| tree[c].exhausted);
+                let e = &mut tree[i];
+                e.exhausted = exhausted;
+                e.visits += 1;
+                if feasible {
+                    e.feasible += 1;
+                    e.sum += obj.into();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::cnf::SatObj;
+    use crate::sat::maxsat::MaxSat;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn proves_optimality() {
+        for seed in 0..5 {
+            let p = problem(seed, 8);
+            let best = optimum(&p);
+            let sk = run(
+                MonteCarloTreeSearch::<_, Assign, _, _>::new(
+                    0.7,
+                    RandomRollout,
+                    Splitmix64::from_u64(seed),
+                ),
+                &p,
+                u64::MAX,
+            );
+            assert_eq!(sk.best_obj(), best);
+            assert_eq!(sk.dual_bound, best);
+            let sk = run(
+                MonteCarloTreeSearch::<_, Assign, _, _>::new(
+                    0.7,
+                    RollbackRollout,
+                    Splitmix64::from_u64(seed),
+                ),
+                &p,
+                u64::MAX,
+            );
+            assert_eq!(sk.best_obj(), best);
+            assert_eq!(sk.dual_bound, best);
+        }
+    }
+
+    #[test]
+    fn uct() {
+        let p = problem(0, 4);
+        let mut e =
+            MonteCarloTreeSearch::<_, Assign, RandomRollout, Splitmix64>::entry(&Assign(p), vec![]);
+        // rollouts of 2, 4, 6 and an unfeasible one, between the best 2 and the worst 10
+        e.visits = 4;
+        e.feasible = 3;
+        e.sum = 12.0;
+        let ln_parent = 16f64.ln();
+        let value = (3.0 * 10.0 - 12.0) / 8.0 / 4.0;
+        for c in [0.0, 0.7, 2.0] {
+            let expected = value + c * (ln_parent / 4.0).sqrt();
+            assert!((e.uct(ln_parent, 10.0, 2.0, c) - expected).abs() < 1e-12);
+        }
+        // a single feasible objective counts as the best one
+        assert_eq!(e.uct(ln_parent, 2.0, 2.0, 0.0), 0.75);
+    }
+
+    thread_local! {
+        static CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// node of Counting, counting its clones
+    #[derive(Debug)]
+    struct Counted(Vec<bool>);
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|c| c.set(c.get() + 1));
+            Self(self.0.clone())
+        }
+    }
+    /// Assign with counted nodes
+    #[derive(Clone)]
+    struct Counting(Assign);
+    impl Tree<MaxSat> for Counting {
+        type Node = Counted;
+        fn root(&self) -> Counted {
+            Counted(self.0.root())
+        }
+        fn objective(&self, n: &Counted) -> Option<SatObj> {
+            self.0.objective(&n.0)
+        }
+        fn to_solution(&self, n: &Counted) -> Option<Vec<bool>> {
+            self.0.to_solution(&n.0)
+        }
+        fn from(p: &MaxSat) -> Self {
+            Self(Assign(p.clone()))
+        }
+    }
+    impl TreeIndirect<MaxSat> for Counting {
+        type ChildId = bool;
+        fn children_id(&self, n: &Counted) -> impl Iterator<Item = bool> {
+            self.0.children_id(&n.0)
+        }
+        fn child(&self, n: &Counted, cid: &bool) -> Counted {
+            Counted(self.0.child(&n.0, cid))
+        }
+    }
+    impl TreeRollback<MaxSat> for Counting {
+        type RollbackInfo = ();
+        fn rollback(&self, n: Counted, info: ()) -> Counted {
+            Counted(self.0.rollback(n.0, info))
+        }
+    }
+    impl TreeRollbackDirectRandom<MaxSat> for Counting {
+        fn random_child_rollback<R: rng::Rng>(
+            &self,
+            n: Counted,
+            rng: &mut R,
+        ) -> Option<(Counted, ())> {
+            self.0
+                .random_child_rollback(n.0, rng)
+                .map(|(n, info)| (Counted(n), info))
+        }
+    }
+
+    #[test]
+    fn rollback_rollout_without_clones() {
+        let p = problem(0, 10);
+        let ts = Counting(Assign(p.clone()));
+        let mut rng = Splitmix64::from_u64(0);
+        let mut sk = SimpleSolutionKeeper::default();
+        let n = ts.child(&ts.root(), &true);
+        let (n, obj) = RollbackRollout.rollout(&ts, n, &mut sk, &mut rng);
+        assert_eq!(n.unwrap().0, [true]);
+        assert_eq!(obj, sk.best_obj());
+        let sk = run(
+            MonteCarloTreeSearch::<_, Counting, _, _>::new(
+                0.7,
+                RollbackRollout,
+                Splitmix64::from_u64(0),
+            ),
+            &p,
+            200,
+        );
+        assert!(sk.best_solution().is_some());
+        assert_eq!(CLONES.with(|c| c.get()), 0);
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+
+use crate::core::tree_space::*;
+use crate::core::*;
+use crate::solver::ant_colony::DecisionKey;
+use crate::solver::monte_carlo_tree_search::{report, Rollout};
+
+/// nested monte carlo search: at level l each child of the current node is evaluated by a
+/// search of level l - 1 (a rollout at level 0) and the walk follows the best sequence of moves
+/// found so far, or the best child of the step when the best solution was found by a rollout
+/// or above the current node. the search of the given level is restarted until the stop
+/// condition fires
+#[derive(Clone)]
+pub struct NestedMonteCarloSearch<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng>
+{
+    level: usize,
+    rollout: RO,
+    rng: R,
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng>
+    NestedMonteCarloSearch<P, TS, RO, R>
+{
+    pub fn new(level: usize, rollout: RO, rng: R) -> Self {
+        Self {
+            level,
+            rollout,
+            rng,
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+    /// best objective found from n and the moves leading to it, None if stopped
+    fn nested<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        ts: &TS,
+        n: TS::Node,
+        level: usize,
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> Option<(P::Obj, Vec<TS::ChildId>)> {
+        if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+            return None;
+        }
+        if level == 0 {
+            sk.iter();
+            let (_, obj) = self.rollout.rollout(ts, n, sk, &mut self.rng);
+            return Some((obj, vec![]));
+        }
+        let mut n = n;
+        let mut played = vec![];
+        let mut best_obj = P::Obj::unfeas();
+        let mut best_moves = vec![];
+        loop {
+            let obj = report(ts, &n, sk);
+            if obj < best_obj {
+                best_obj = obj;
+                best_moves = played.clone();
+            }
+            let mut step_best: Option<(P::Obj, TS::ChildId)> = None;
+            for cid in ts.children_id(&n).collect::<Vec<_>>() {
+                let c = ts.child(&n, &cid);
+                let (obj, moves) = self.nested(ts, c, level - 1, sk, stop)?;
+                if obj < best_obj {
+                    best_obj = obj;
+                    best_moves = played.clone();
+                    best_moves.push(cid.clone());
+                    best_moves.extend(moves);
+                }
+                if step_best.as_ref().is_none_or(|s| obj < s.0) {
+                    step_best = Some((obj, cid));
+                }
+            }
+            let cid = match step_best {
+                None => break,
+                Some(_) if best_moves.len() > played.len() => best_moves[played.len()].clone(),
+                Some((_, cid)) => cid,
+            };
+            n = ts.child(&n, &cid);
+            played.push(cid);
+        }
+        Some((best_obj, best_moves))
+    }
+}
+impl<P: Problem, TS: TreeIndirect<P>, RO: Rollout<P, TS>, R: rng::Rng> Solver<P>
+    for NestedMonteCarloSearch<P, TS, RO, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let root = ts.root();
+        if ts.children_id(&root).next().is_none() {
+            report(&ts, &root, sk);
+            return;
+        }
+        while self
+            .nested(&ts, ts.root(), self.level, sk, &mut stop)
+            .is_some()
+        {}
+    }
+}
+
+/// nested rollout policy adaptation: the playouts choose each child with probability
+/// proportional to exp of the weight of its decision key, at level l the policy is adapted
+/// towards the best sequence found by `iterations` searches of level l - 1, each one starting
+/// from the current policy. the search of the given level is restarted with a uniform policy
+/// until the stop condition fires
+#[derive(Clone)]
+pub struct NestedRolloutPolicyAdaptation<
+    P: Problem,
+    TS: TreeIndirect<P>,
+    K: DecisionKey<P, TS>,
+    R: rng::Rng,
+> {
+    key: K,
+    level: usize,
+    iterations: usize,
+    /// learning rate of the adaptation
+    alpha: f64,
+    rng: R,
+    _p: std::marker::PhantomData<P>,
+    _ts: std::marker::PhantomData<TS>,
+}
+impl<P: Problem, TS: TreeIndirect<P>, K: DecisionKey<P, TS>, R: rng::Rng>
+    NestedRolloutPolicyAdaptation<P, TS, K, R>
+{
+    pub fn new(key: K, level: usize, iterations: usize, alpha: f64, rng: R) -> Self {
+        debug_assert!(level > 0 && iterations > 0);
+        Self {
+            key,
+            level,
+            iterations,
+            alpha,
+            rng,
+            _p: std::marker::PhantomData,
+            _ts: std::marker::PhantomData,
+        }
+    }
+    /// probabilities of the children of n under the policy, along with their keys
+    fn probabilities(
+        &self,
+        ts: &TS,
+        n: &TS::Node,
+        children: &[TS::ChildId],
+        policy: &HashMap<K::Key, f64>,
+    ) -> (Vec<K::Key>, Vec<f64>) {
+        let keys: Vec<K::Key> = children
+            .iter()
+            .map(|cid| self.key.key(ts, n, cid))
+            .collect();
+        let weights: Vec<f64> = keys
+            .iter()
+            .map(|k| policy.get(k).copied().unwrap_or(0.0))
+            .collect();
+        let max = weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let exp: Vec<f64> = weights.iter().map(|w| (w - max).exp()).collect();
+        let z: f64 = exp.iter().sum();
+        (keys, exp.into_iter().map(|e| e / z).collect())
+    }
+    /// returns the best objective met by the playout and the moves leading to it
+    fn playout<SK: SolutionKeeper<P>>(
+        &mut self,
+        ts: &TS,
+        policy: &HashMap<K::Key, f64>,
+        sk: &mut SK,
+    ) -> (P::Obj, Vec<TS::ChildId>) {
+        let mut n = ts.root();
+        let mut moves = vec![];
+        let mut best = (P::Obj::unfeas(), 0);
+        loop {
+            let obj = report(ts, &n, sk);
+            if obj < best.0 {
+                best = (obj, moves.len());
+            }
+            let children: Vec<TS::ChildId> = ts.children_id(&n).collect();
+            if children.is_empty() {
+                break;
+            }
+            let (_, probabilities) = self.probabilities(ts, &n, &children, policy);
+            let mut x = self.rng.next01();
+            let i = probabilities
+                .iter()
+                .position(|&q| {
+                    x -= q;
+                    x < 0.0
+                })
+                .unwrap_or(children.len() - 1);
+            n = ts.child(&n, &children[i]);
+            moves.push(children[i].clone());
+        }
+        moves.truncate(best.1);
+        (best.0, moves)
+    }
+    fn adapt(&self, ts: &TS, policy: &mut HashMap<K::Key, f64>, moves: &[TS::ChildId]) {
+        let old = policy.clone();
+        let mut n = ts.root();
+        for cid in moves {
+            let children: Vec<TS::ChildId> = ts.children_id(&n).collect();
+            let (keys, probabilities) = self.probabilities(ts, &n, &children, &old);
+            for (k, q) in keys.into_iter().zip(probabilities) {
+                *policy.entry(k).or_default() -= self.alpha * q;
+            }
+            *policy.entry(self.key.key(ts, &n, cid)).or_default() += self.alpha;
+            n = ts.child(&n, cid);
+        }
+    }
+    /// None if stopped
+    fn nested<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        ts: &TS,
+        level: usize,
+        mut policy: HashMap<K::Key, f64>,
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> Option<(P::Obj, Vec<TS::ChildId>)> {
+        if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+            return None;
+        }
+        if level == 0 {
+            sk.iter();
+            return Some(self.playout(ts, &policy, sk));
+        }
+        let mut best = (P::Obj::unfeas(), vec![]);
+        for _ in 0..self.iterations {
+            let result = self.nested(ts, level - 1, policy.clone(), sk, stop)?;
+            if result.0 <= best.0 {
+                best = result;
+            }
+            self.adapt(ts, &mut policy, &best.1);
+        }
+        Some(best)
+    }
+}
+impl<P: Problem, TS: TreeIndirect<P>, K: DecisionKey<P, TS>, R: rng::Rng> Solver<P>
+    for NestedRolloutPolicyAdaptation<P, TS, K, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let ts = TS::from(&p);
+        let root = ts.root();
+        if ts.children_id(&root).next().is_none() {
+            report(&ts, &root, sk);
+            return;
+        }
+        while self
+            .nested(&ts, self.level, HashMap::new(), sk, &mut stop)
+            .is_some()
+        {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::cnf::SatObj;
+    use crate::solver::monte_carlo_tree_search::RandomRollout;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn nested_level_enumerates() {
+        // a search of level l is exact on l remaining variables
+        let p = problem(0, 4);
+        let ts = Assign(p.clone());
+        let mut nmcs = NestedMonteCarloSearch::<_, Assign, _, _>::new(
+            4,
+            RandomRollout,
+            Splitmix64::from_u64(0),
+        );
+        let mut sk = SimpleSolutionKeeper::default();
+        let (obj, moves) = nmcs
+            .nested(&ts, ts.root(), 4, &mut sk, &mut CallStop(u64::MAX))
+            .unwrap();
+        assert_eq!(obj, optimum(&p));
+        assert_eq!(p.obj(&moves), obj);
+        // a level 0 search is a single rollout
+        let mut sk = SimpleSolutionKeeper::default();
+        let mut stop = CallStop(u64::MAX);
+        let (_, moves) = nmcs.nested(&ts, ts.root(), 0, &mut sk, &mut stop).unwrap();
+        assert!(moves.is_empty());
+        assert_eq!(stop.0, u64::MAX - 1);
+    }
+
+    #[test]
+    fn policy_adapts_to_best_sequence() {
+        let key = |_: &Assign, n: &Vec<bool>, cid: &bool| (n.len(), *cid);
+        let nrpa = NestedRolloutPolicyAdaptation::<_, Assign, _, _>::new(
+            key,
+            1,
+            1,
+            1.0,
+            Splitmix64::from_u64(0),
+        );
+        let ts = Assign(costs(&[1, 2, 4]));
+        let best = [true, false, true];
+        let mut policy = HashMap::new();
+        nrpa.adapt(&ts, &mut policy, &best);
+        // both children were equally likely, the chosen one gains alpha / 2 and the other loses it
+        for (depth, &value) in best.iter().enumerate() {
+            assert_eq!(policy[&(depth, value)], 0.5);
+            assert_eq!(policy[&(depth, !value)], -0.5);
+        }
+        // each adaptation makes every decision of the sequence more likely
+        let mut previous = 0.5;
+        for _ in 0..5 {
+            let mut n = ts.root();
+            let mut q = 0.0;
+            for &value in &best {
+                let (_, probabilities) = nrpa.probabilities(&ts, &n, &[false, true], &policy);
+                q = probabilities[value as usize];
+                assert!(q > previous);
+                n = ts.child(&n, &value);
+            }
+            previous = q;
+            nrpa.adapt(&ts, &mut policy, &best);
+        }
+    }
+
+    #[test]
+    fn root_without_children() {
+        let p = problem(0, 0);
+        let key = |_: &Assign, n: &Vec<bool>, cid: &bool| (n.len(), *cid);
+        let sk = run(
+            NestedMonteCarloSearch::<_, Assign, _, _>::new(
+                2,
+                RandomRollout,
+                Splitmix64::from_u64(0),
+            ),
+            &p,
+            u64::MAX,
+        );
+        assert_eq!(sk.best_obj(), SatObj(0));
+        let sk = run(
+            NestedRolloutPolicyAdaptation::<_, Assign, _, _>::new(
+                key,
+                2,
+                30,
+                1.0,
+                Splitmix64::from_u64(0),
+            ),
+            &p,
+            u64::MAX,
+        );
+        assert_eq!(sk.best_obj(), SatObj(0));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::core::*;
+use crate::solver::acceptance::Acceptance;
+use crate::solver::simulated_annealing::{ConstantTemperature, MetropolisAcceptance};
+use neighbour_space::*;
+
+/// number of exchange rounds between two adjustments of an adaptive ladder
+const ADAPT_ROUNDS: u64 = 100;
+
+/// how the temperatures of the replicas evolve
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ladder {
+    Fixed,
+    /// the lowest and highest temperatures are kept and the others are moved so that the
+    /// exchanges between adjacent replicas are accepted at the same rate
+    Adaptive,
+}
+
+/// temperatures from min_temp to max_temp in geometric progression
+pub fn geometric_ladder(min_temp: f64, max_temp: f64, replicas: usize) -> Vec<f64> {
+    debug_assert!(0.0 < min_temp && min_temp <= max_temp && replicas > 0);
+    if replicas == 1 {
+        return vec![min_temp];
+    }
+    let ratio = (max_temp / min_temp).powf(1.0 / (replicas - 1) as f64);
+    (0..replicas)
+        .map(|i| min_temp * ratio.powi(i as i32))
+        .collect()
+}
+
+/// replica exchange: one Metropolis chain per temperature, each doing sweep_length steps in turn,
+/// after which adjacent replicas (alternately the even and the odd pairs) are swapped with
+/// probability min(1, exp((1/t_i - 1/t_j)(obj_i - obj_j)))
+#[derive(Clone)]
+pub struct ParallelTempering<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    temperatures: Vec<f64>,
+    sweep_length: usize,
+    ladder: Ladder,
+    rng: R,
+    ns: N,
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> ParallelTempering<P, N, R> {
+    /// temperatures must be positive and increasing
+    pub fn new(
+        ns: N,
+        initial_solution: (P::Sol, P::Obj),
+        temperatures: Vec<f64>,
+        sweep_length: usize,
+        ladder: Ladder,
+        rng: R,
+    ) -> Self {
+        debug_assert!(!temperatures.is_empty() && temperatures[0] > 0.0);
+        debug_assert!(temperatures.windows(2).all(|w| w[0] <= w[1]));
+        debug_assert!(sweep_length > 0);
+        Self {
+            initial_solution: Some(initial_solution),
+            temperatures,
+            sweep_length,
+            ladder,
+            rng,
+            ns,
+        }
+    }
+}
+
+/// probability min(1, exp((1/ti - 1/tj)(ei - ej))) to swap the solutions of objectives ei and ej
+/// between the replicas at temperatures ti and tj
+fn swap_probability(ti: f64, tj: f64, ei: f64, ej: f64) -> f64 {
+    ((1.0 / ti - 1.0 / tj) * (ei - ej)).exp().min(1.0)
+}
+
+/// spreads the logarithms of the temperatures so that the gaps with a high exchange rate grow
+fn adapt_ladder(temperatures: &mut [f64], attempts: &[u64], accepted: &[u64]) {
+    let n = temperatures.len();
+    let rates: Vec<f64> = (0..n - 1)
+        .map(|i| (accepted[i] as f64 + 1.0) / (attempts[i] as f64 + 2.0))
+        .collect();
+    let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+    let mut gaps: Vec<f64> = (0..n - 1)
+        .map(|i| (temperatures[i + 1] / temperatures[i]).ln() * rates[i] / mean)
+        .collect();
+    let total: f64 = gaps.iter().sum();
+    let range = (temperatures[n - 1] / temperatures[0]).ln();
+    if total <= 0.0 {
+        return;
+    }
+    for g in gaps.iter_mut() {
+        *g *= range / total;
+    }
+    let mut log_temp = temperatures[0].ln();
+    for i in 1..n - 1 {
+        log_temp += gaps[i - 1];
+        temperatures[i] = log_temp.exp();
+    }
+}
+
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng> Solver<P>
+    for ParallelTempering<P, N, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let initial_solution = self.initial_solution.take().unwrap();
+        sk.add_solution(&initial_solution.0, initial_solution.1);
+        let n = self.temperatures.len();
+        // replicas[i] runs at temperatures[i]
+        let mut replicas = vec![initial_solution; n];
+        let mut attempts = vec![0u64; n.saturating_sub(1)];
+        let mut accepted = vec![0u64; n.saturating_sub(1)];
+        let mut round = 0u64;
+        let chains = |temperatures: &[f64]| -> Vec<_> {
+            temperatures
+                .iter()
+                .map(|&temp| MetropolisAcceptance::new(ConstantTemperature(temp)))
+                .collect()
+        };
+        let mut metropolis = chains(&self.temperatures);
+        'outer: loop {
+            let mut swept = Vec::with_capacity(n);
+            for ((mut solution, mut obj), metropolis) in replicas.into_iter().zip(&mut metropolis) {
+                for _ in 0..self.sweep_length {
+                    if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                        break 'outer;
+                    }
+                    sk.iter();
+                    metropolis.next_iteration(obj.into(), None);
+                    let nid = self.ns.random_neighbour_id(&p, &solution, &mut self.rng);
+                    let nobj = self.ns.neighbour_obj(&p, &solution, &nid);
+                    let best = sk.best_obj().into();
+                    if metropolis.accept(obj.into(), nobj.into(), best, &mut self.rng) {
+                        solution = self.ns.random_neighbour(&p, solution, nid);
+                        obj = nobj;
+                        sk.add_solution(&solution, obj);
+                    }
+                }
+                swept.push((solution, obj));
+            }
+            replicas = swept;
+            for i in (round as usize % 2..n.saturating_sub(1)).step_by(2) {
+                let (ti, tj) = (self.temperatures[i], self.temperatures[i + 1]);
+                let (ei, ej) = (replicas[i].1.into(), replicas[i + 1].1.into());
+                attempts[i] += 1;
+                let q = swap_probability(ti, tj, ei, ej);
+                if q >= 1.0 || self.rng.next01() < q {
+                    replicas.swap(i, i + 1);
+                    accepted[i] += 1;
+                }
+            }
+            round += 1;
+            if self.ladder == Ladder::Adaptive && n > 2 && round.is_multiple_of(ADAPT_ROUNDS) {
+                adapt_ladder(&mut self.temperatures, &attempts, &accepted);
+                metropolis = chains(&self.temperatures);
+                attempts.fill(0);
+                accepted.fill(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_probability_formula() {
+        for (ti, tj, ei, ej) in [
+            (1.0f64, 2.0, 3.0, 5.0),
+            (0.5, 4.0, 10.0, 7.5),
+            (2.0, 3.0, 1.0, 1.0),
+        ] {
+            let expected = ((1.0 / ti - 1.0 / tj) * (ei - ej)).exp().min(1.0);
+            assert!((swap_probability(ti, tj, ei, ej) - expected).abs() < 1e-12);
+        }
+        // giving the better solution to the colder replica is always accepted
+        assert_eq!(swap_probability(1.0, 2.0, 5.0, 3.0), 1.0);
+        assert!((swap_probability(1.0, 2.0, 3.0, 5.0) - (-1.0f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn adapt_ladder_widens_accepted_gaps() {
+        let mut temperatures = geometric_ladder(1.0, 16.0, 5);
+        adapt_ladder(&mut temperatures, &[100; 4], &[90, 10, 10, 10]);
+        assert!((temperatures[0] - 1.0).abs() < 1e-12 && (temperatures[4] - 16.0).abs() < 1e-9);
+        assert!(temperatures.windows(2).all(|w| w[0] < w[1]));
+        // the first gap was accepted the most and widens, the others shrink
+        assert!(temperatures[1] / temperatures[0] > 2.0);
+        for i in 1..4 {
+            assert!(temperatures[i + 1] / temperatures[i] < 2.0);
+        }
+        // equal rates keep the ladder
+        let mut temperatures = geometric_ladder(1.0, 16.0, 5);
+        adapt_ladder(&mut temperatures, &[100; 4], &[30; 4]);
+        for (t, e) in temperatures.iter().zip(geometric_ladder(1.0, 16.0, 5)) {
+            assert!((t - e).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn geometric_ladder_bounds() {
+        let ladder = geometric_ladder(0.5, 8.0, 5);
+        assert_eq!(ladder.len(), 5);
+        assert!((ladder[0] - 0.5).abs() < 1e-12 && (ladder[4] - 8.0).abs() < 1e-9);
+        assert!((ladder[2] - 2.0).abs() < 1e-9);
+    }
+}
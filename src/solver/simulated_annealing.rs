@@ -1,8 +1,57 @@
 use crate::core::*;
+use crate::solver::acceptance::*;
 use neighbour_space::*;
 
+/// multiplier applied by AdaptiveCooling to the temperature when the acceptance rate is off target
+const ADAPTIVE_FACTOR: f64 = 1.1;
+/// number of random neighbours sampled by SimulatedAnnealing::calibrated
+const CALIBRATION_SAMPLES: usize = 1000;
+
+/// temperature at each iteration, given the objective of the current solution
 pub trait CoolingSchedule: Clone {
     fn temperature(&mut self, obj: f64) -> f64;
+    /// called before each temperature with the fraction of the budget used,
+    /// only if the stop condition reports it
+    fn set_progress(&mut self, _progress: f64) {}
+    /// called after each temperature with whether the neighbour was accepted
+    fn observe_acceptance(&mut self, _accepted: bool) {}
+}
+
+/// accepts improving neighbours and worsening ones with probability exp(-delta / temperature),
+/// the search stops once the temperature is not positive
+#[derive(Clone)]
+pub struct MetropolisAcceptance<CS: CoolingSchedule> {
+    cooling_schedule: CS,
+    temp: f64,
+}
+impl<CS: CoolingSchedule> MetropolisAcceptance<CS> {
+    pub fn new(cooling_schedule: CS) -> Self {
+        Self {
+            cooling_schedule,
+            temp: 0.0,
+        }
+    }
+}
+impl<CS: CoolingSchedule> Acceptance for MetropolisAcceptance<CS> {
+    fn next_iteration(&mut self, current: f64, progress: Option<f64>) -> bool {
+        if let Some(progress) = progress {
+            self.cooling_schedule.set_progress(progress);
+        }
+        self.temp = self.cooling_schedule.temperature(current);
+        self.temp > 0.0
+    }
+    fn accept<R: rng::Rng>(
+        &mut self,
+        current: f64,
+        candidate: f64,
+        _best: f64,
+        rng: &mut R,
+    ) -> bool {
+        let delta = candidate - current;
+        let accepted = delta < 0.0 || rng.next01() < (-delta / self.temp).exp();
+        self.cooling_schedule.observe_acceptance(accepted);
+        accepted
+    }
 }
 
 #[derive(Clone)]
@@ -11,22 +60,43 @@ pub struct SimulatedAnnealing<
     N: NeighbourhoodIndirectRandom<P>,
     R: rng::Rng,
     CS: CoolingSchedule,
-> {
-    initial_solution: Option<(P::Sol, P::Obj)>,
-    rng: R,
-    cooling_schedule: CS,
-    ns: N,
-}
+>(AcceptanceSearch<P, N, R, MetropolisAcceptance<CS>>);
 impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng, CS: CoolingSchedule>
     SimulatedAnnealing<P, N, R, CS>
 {
     pub fn new(ns: N, initial_solution: (P::Sol, P::Obj), rng: R, cooling_schedule: CS) -> Self {
-        Self {
-            initial_solution: Some(initial_solution),
-            rng,
-            cooling_schedule,
+        Self(
+            AcceptanceSearch::new(
+                ns,
+                initial_solution,
+                MetropolisAcceptance::new(cooling_schedule),
+                rng,
+            )
+            .without_initial_report(),
+        )
+    }
+    /// the schedule is built from the temperatures at which worsening random neighbours of the
+    /// initial solution are accepted with the initial and final probabilities, e.g.
+    /// `|t0, t1| TimeFractionCooling::new(t0, t1, Interpolation::Geometric)` or
+    /// `|t0, _| ArithmeticGeometricCooling::new(t0, 0.999, 0.0)`
+    pub fn calibrated<F: FnOnce(f64, f64) -> CS>(
+        p: &P,
+        ns: N,
+        initial_solution: (P::Sol, P::Obj),
+        initial_acceptance: f64,
+        final_acceptance: f64,
+        schedule: F,
+        mut rng: R,
+    ) -> Self {
+        let deltas = sample_deltas(p, &ns, &initial_solution, CALIBRATION_SAMPLES, &mut rng);
+        let initial_temp = temperature_for_acceptance(&deltas, initial_acceptance);
+        let final_temp = temperature_for_acceptance(&deltas, final_acceptance);
+        Self::new(
             ns,
-        }
+            initial_solution,
+            rng,
+            schedule(initial_temp, final_temp),
+        )
     }
 }
 impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng, CS: CoolingSchedule> Solver<P>
@@ -36,30 +106,65 @@ impl<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng, CS: CoolingSche
         &mut self,
         p: P,
         sk: &mut SK,
-        mut stop: S,
+        stop: S,
     ) {
-        let (mut current_solution, mut current_obj) = self.initial_solution.take().unwrap();
-        loop {
-            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
-                break;
-            }
-            sk.iter();
-            let temp = self.cooling_schedule.temperature(current_obj.into());
-            if temp <= 0.0 {
-                break;
-            }
-            let nid = self
-                .ns
-                .random_neighbour_id(&p, &current_solution, &mut self.rng);
-            let nobj = self.ns.neighbour_obj(&p, &current_solution, &nid);
-            let delta = nobj.into() - current_obj.into();
-            if delta < 0.0 || self.rng.next01() < (-delta / temp).exp() {
-                current_solution = self.ns.random_neighbour(&p, current_solution, nid);
-                current_obj = nobj;
-                sk.add_solution(&current_solution, current_obj);
-            }
+        self.0.solve(p, sk, stop)
+    }
+}
+
+/// objective increase of random neighbours of the solution, improving ones are not kept
+fn sample_deltas<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>(
+    p: &P,
+    ns: &N,
+    (solution, obj): &(P::Sol, P::Obj),
+    samples: usize,
+    rng: &mut R,
+) -> Vec<f64> {
+    let obj: f64 = (*obj).into();
+    (0..samples)
+        .map(|_| {
+            let nid = ns.random_neighbour_id(p, solution, rng);
+            ns.neighbour_obj(p, solution, &nid).into() - obj
+        })
+        .filter(|d| *d > 0.0 && d.is_finite())
+        .collect()
+}
+
+/// temperature at which worsening moves of the given objective increases are accepted
+/// with the given average probability, 1 if there are none
+pub fn temperature_for_acceptance(deltas: &[f64], acceptance: f64) -> f64 {
+    debug_assert!(acceptance > 0.0 && acceptance < 1.0);
+    if deltas.is_empty() {
+        return 1.0;
+    }
+    let mean_acceptance =
+        |t: f64| deltas.iter().map(|d| (-d / t).exp()).sum::<f64>() / deltas.len() as f64;
+    // the mean acceptance increases with the temperature, bisection on its logarithm
+    let min = deltas.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = deltas.iter().copied().fold(0.0, f64::max);
+    let (mut lo, mut hi) = (min.ln() - 10.0, max.ln() + 10.0);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if mean_acceptance(mid.exp()) < acceptance {
+            lo = mid;
+        } else {
+            hi = mid;
         }
     }
+    ((lo + hi) / 2.0).exp()
+}
+
+/// initial temperature such that worsening random neighbours of the solution are accepted
+/// with the given average probability, from the given number of samples
+pub fn estimate_temperature<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>(
+    p: &P,
+    ns: &N,
+    solution: &(P::Sol, P::Obj),
+    acceptance: f64,
+    samples: usize,
+    rng: &mut R,
+) -> f64 {
+    temperature_for_acceptance(&sample_deltas(p, ns, solution, samples, rng), acceptance)
 }
 
 /// arithmetic if a=1
@@ -90,4 +195,342 @@ impl CoolingSchedule for ArithmeticGeometricCooling {
     }
 }
 
-// TODO: more cooling schedules, including adaptive ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    /// linear in the logarithm of the temperature
+    Geometric,
+}
+
+/// always the same temperature
+#[derive(Clone)]
+pub struct ConstantTemperature(pub f64);
+impl CoolingSchedule for ConstantTemperature {
+    fn temperature(&mut self, _obj: f64) -> f64 {
+        self.0
+    }
+}
+
+/// goes from initial_temp to final_temp over the budget of the stop condition so that it does
+/// not depend on the iteration speed
+#[derive(Clone)]
+pub struct TimeFractionCooling {
+    initial_temp: f64,
+    final_temp: f64,
+    interpolation: Interpolation,
+    progress: Option<f64>,
+}
+impl TimeFractionCooling {
+    /// the stop condition must report its progress, e.g. TimeStop, there is no cooling otherwise
+    /// (debug builds panic)
+    pub fn new(initial_temp: f64, final_temp: f64, interpolation: Interpolation) -> Self {
+        debug_assert!(initial_temp > 0.0);
+        debug_assert!(final_temp > 0.0 || interpolation == Interpolation::Linear);
+        Self {
+            initial_temp,
+            final_temp,
+            interpolation,
+            progress: None,
+        }
+    }
+}
+impl CoolingSchedule for TimeFractionCooling {
+    fn set_progress(&mut self, progress: f64) {
+        self.progress = Some(progress);
+    }
+    fn temperature(&mut self, _obj: f64) -> f64 {
+        debug_assert!(
+            self.progress.is_some(),
+            "TimeFractionCooling needs a stop condition reporting its progress"
+        );
+        let x = self.progress.unwrap_or(0.0);
+        match self.interpolation {
+            Interpolation::Linear => self.initial_temp + (self.final_temp - self.initial_temp) * x,
+            Interpolation::Geometric => {
+                self.initial_temp * (self.final_temp / self.initial_temp).powf(x)
+            }
+        }
+    }
+}
+
+/// Lundy–Mees: t <- t / (1 + beta * t) at each iteration
+#[derive(Clone)]
+pub struct LundyMeesCooling {
+    temp: f64,
+    beta: f64,
+}
+impl LundyMeesCooling {
+    pub fn new(initial_temp: f64, beta: f64) -> Self {
+        debug_assert!(initial_temp > 0.0);
+        debug_assert!(beta >= 0.0);
+        Self {
+            temp: initial_temp,
+            beta,
+        }
+    }
+    /// beta such that the temperature goes from initial_temp to final_temp in iterations steps
+    pub fn with_iterations(initial_temp: f64, final_temp: f64, iterations: u64) -> Self {
+        debug_assert!(final_temp > 0.0 && iterations > 0);
+        let beta = (initial_temp - final_temp) / (iterations as f64 * initial_temp * final_temp);
+        Self::new(initial_temp, beta)
+    }
+}
+impl CoolingSchedule for LundyMeesCooling {
+    fn temperature(&mut self, _obj: f64) -> f64 {
+        let t = self.temp;
+        self.temp /= 1.0 + self.beta * self.temp;
+        t
+    }
+}
+
+/// restarts the schedule from its initial state when the objective has not improved
+/// for patience iterations. if the stop condition reports its progress, the progress given to
+/// the schedule is rescaled to go from 0 at the last reheat to 1 at the end of the budget so
+/// that time based schedules restart too.
+/// the temperatures are multiplied by reheat_ratio at each reheat, a ratio below 1 makes
+/// successive restarts start colder
+#[derive(Clone)]
+pub struct ReheatingCooling<CS: CoolingSchedule> {
+    initial: CS,
+    schedule: CS,
+    patience: u64,
+    reheat_ratio: f64,
+    scale: f64,
+    best: f64,
+    since_best: u64,
+    progress: Option<f64>,
+    /// progress at the last reheat
+    reheat_progress: f64,
+}
+impl<CS: CoolingSchedule> ReheatingCooling<CS> {
+    pub fn new(schedule: CS, patience: u64, reheat_ratio: f64) -> Self {
+        debug_assert!(patience > 0);
+        debug_assert!(reheat_ratio > 0.0);
+        Self {
+            initial: schedule.clone(),
+            schedule,
+            patience,
+            reheat_ratio,
+            scale: 1.0,
+            best: f64::INFINITY,
+            since_best: 0,
+            progress: None,
+            reheat_progress: 0.0,
+        }
+    }
+}
+impl<CS: CoolingSchedule> CoolingSchedule for ReheatingCooling<CS> {
+    fn set_progress(&mut self, progress: f64) {
+        self.progress = Some(progress);
+    }
+    fn temperature(&mut self, obj: f64) -> f64 {
+        if obj < self.best {
+            self.best = obj;
+            self.since_best = 0;
+        } else {
+            self.since_best += 1;
+            if self.since_best >= self.patience {
+                self.schedule = self.initial.clone();
+                self.scale *= self.reheat_ratio;
+                self.since_best = 0;
+                if let Some(x) = self.progress {
+                    self.reheat_progress = x;
+                }
+            }
+        }
+        if let Some(x) = self.progress {
+            let left = 1.0 - self.reheat_progress;
+            self.schedule.set_progress(if left > 0.0 {
+                ((x - self.reheat_progress) / left).clamp(0.0, 1.0)
+            } else {
+                1.0
+            });
+        }
+        self.scale * self.schedule.temperature(obj)
+    }
+    fn observe_acceptance(&mut self, accepted: bool) {
+        self.schedule.observe_acceptance(accepted);
+    }
+}
+
+/// adjusts the temperature every window iterations so that the acceptance rate follows the
+/// target, interpolated from initial_rate to final_rate over the budget
+#[derive(Clone)]
+pub struct AdaptiveCooling {
+    temp: f64,
+    initial_rate: f64,
+    final_rate: f64,
+    window: u64,
+    progress: Option<f64>,
+    iterations: u64,
+    accepted: u64,
+}
+impl AdaptiveCooling {
+    /// the stop condition must report its progress unless both rates are equal, the target stays
+    /// at initial_rate otherwise (debug builds panic)
+    pub fn new(initial_temp: f64, initial_rate: f64, final_rate: f64, window: u64) -> Self {
+        debug_assert!(initial_temp > 0.0);
+        debug_assert!((0.0..=1.0).contains(&initial_rate) && (0.0..=1.0).contains(&final_rate));
+        debug_assert!(window > 0);
+        Self {
+            temp: initial_temp,
+            initial_rate,
+            final_rate,
+            window,
+            progress: None,
+            iterations: 0,
+            accepted: 0,
+        }
+    }
+}
+impl CoolingSchedule for AdaptiveCooling {
+    fn set_progress(&mut self, progress: f64) {
+        self.progress = Some(progress);
+    }
+    fn temperature(&mut self, _obj: f64) -> f64 {
+        self.temp
+    }
+    fn observe_acceptance(&mut self, accepted: bool) {
+        self.iterations += 1;
+        self.accepted += accepted as u64;
+        if self.iterations == self.window {
+            debug_assert!(
+                self.progress.is_some() || self.initial_rate == self.final_rate,
+                "AdaptiveCooling needs a stop condition reporting its progress"
+            );
+            let x = self.progress.unwrap_or(0.0);
+            let target = self.initial_rate + (self.final_rate - self.initial_rate) * x;
+            let rate = self.accepted as f64 / self.window as f64;
+            if rate < target {
+                self.temp *= ADAPTIVE_FACTOR;
+            } else if rate > target {
+                self.temp /= ADAPTIVE_FACTOR;
+            }
+            self.iterations = 0;
+            self.accepted = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::tests::*;
+
+    #[test]
+    fn time_fraction_follows_progress() {
+        let mut cooling = TimeFractionCooling::new(10.0, 0.1, Interpolation::Geometric);
+        for (progress, temp) in [(0.0, 10.0), (0.5, 1.0), (1.0, 0.1)] {
+            cooling.set_progress(progress);
+            assert!((cooling.temperature(0.0) - temp).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "reporting its progress")]
+    fn time_fraction_without_progress() {
+        let p = problem(0, 10);
+        let cooling = TimeFractionCooling::new(10.0, 0.1, Interpolation::Geometric);
+        run(
+            SimulatedAnnealing::new(Flip, all_false(&p), Splitmix64::from_u64(0), cooling),
+            &p,
+            100,
+        );
+    }
+
+    #[test]
+    fn estimated_temperature_hits_acceptance() {
+        let p = problem(1, 12);
+        let initial = all_false(&p);
+        for target in [0.2, 0.5, 0.8] {
+            let mut rng = Splitmix64::from_u64(3);
+            let temp = estimate_temperature(&p, &Flip, &initial, target, 1000, &mut rng);
+            // every worsening flip, weighted by how often a random flip draws it
+            let obj = initial.1 .0 as f64;
+            let deltas: Vec<f64> = (0..p.num_vars)
+                .map(|i| {
+                    NeighbourhoodIndirect::neighbour_obj(&Flip, &p, &initial.0, &i).0 as f64 - obj
+                })
+                .filter(|d| *d > 0.0)
+                .collect();
+            let acceptance =
+                deltas.iter().map(|d| (-d / temp).exp()).sum::<f64>() / deltas.len() as f64;
+            assert!(
+                (acceptance - target).abs() < 0.05,
+                "{} {}",
+                acceptance,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn calibrated_with_any_schedule() {
+        for seed in 0..5 {
+            let p = problem(seed, 14);
+            let sk = run(
+                SimulatedAnnealing::calibrated(
+                    &p,
+                    Flip,
+                    all_false(&p),
+                    0.5,
+                    0.01,
+                    |t0, t1| LundyMeesCooling::with_iterations(t0, t1, 20000),
+                    Splitmix64::from_u64(seed),
+                ),
+                &p,
+                20000,
+            );
+            assert_eq!(sk.best_obj(), optimum(&p));
+        }
+    }
+
+    #[test]
+    fn reheating_restarts_time_fraction() {
+        let schedule = TimeFractionCooling::new(10.0, 0.1, Interpolation::Linear);
+        let mut cooling = ReheatingCooling::new(schedule, 5, 0.5);
+        let mut temperature = |progress| {
+            cooling.set_progress(progress);
+            cooling.temperature(1.0)
+        };
+        assert_eq!(temperature(0.0), 10.0);
+        let mut temp = 0.0;
+        for i in 1..5 {
+            temp = temperature(i as f64 * 0.1);
+        }
+        assert!(temp < 7.0);
+        // the fifth iteration without improvement reheats to half the initial temperature
+        assert_eq!(temperature(0.5), 5.0);
+        assert!((temperature(0.75) - 0.5 * 5.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lundy_mees_reaches_final_temperature() {
+        let mut cooling = LundyMeesCooling::with_iterations(5.0, 0.1, 100);
+        let temps: Vec<f64> = (0..=100).map(|_| cooling.temperature(0.0)).collect();
+        assert_eq!(temps[0], 5.0);
+        assert!(temps.windows(2).all(|w| w[1] < w[0]));
+        assert!((temps[100] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adaptive_follows_target_rate() {
+        let window = |cooling: &mut AdaptiveCooling, accepted: usize| {
+            for i in 0..4 {
+                cooling.observe_acceptance(i < accepted);
+            }
+            cooling.temperature(0.0)
+        };
+        let mut cooling = AdaptiveCooling::new(1.0, 0.5, 0.5, 4);
+        // below the target the temperature rises, above it falls, on target it stays
+        assert!((window(&mut cooling, 1) - ADAPTIVE_FACTOR).abs() < 1e-9);
+        assert!((window(&mut cooling, 3) - 1.0).abs() < 1e-9);
+        assert!((window(&mut cooling, 2) - 1.0).abs() < 1e-9);
+        // the target goes from initial_rate to final_rate with the progress
+        let mut cooling = AdaptiveCooling::new(1.0, 0.8, 0.2, 4);
+        cooling.set_progress(0.0);
+        assert!(window(&mut cooling, 2) > 1.0);
+        cooling.set_progress(1.0);
+        assert!((window(&mut cooling, 2) - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::neighbour_space::*;
+use crate::core::*;
+
+/// attribute of a move that becomes tabu once the move is made,
+/// e.g. the modified variable to forbid changing it back for a while
+pub trait TabuAttribute<P: Problem, N: NeighbourhoodIndirect<P>>: Clone {
+    type Attr: Clone + Eq + Hash;
+    fn attribute(&self, p: &P, node: &P::Sol, nid: &N::NeighbourId) -> Self::Attr;
+}
+impl<P: Problem, N: NeighbourhoodIndirect<P>, A: Clone + Eq + Hash, F> TabuAttribute<P, N> for F
+where
+    F: Fn(&P, &P::Sol, &N::NeighbourId) -> A + Clone,
+{
+    type Attr = A;
+    fn attribute(&self, p: &P, node: &P::Sol, nid: &N::NeighbourId) -> A {
+        self(p, node, nid)
+    }
+}
+
+/// number of iterations an attribute stays tabu
+#[derive(Clone, Copy, Debug)]
+pub enum Tenure {
+    Fixed(u64),
+    /// uniform in min..=max, drawn each time an attribute becomes tabu
+    Random(u64, u64),
+}
+
+/// when a tabu move is allowed anyway
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aspiration {
+    Never,
+    /// the move gives a solution better than the best one found
+    ImprovesBest,
+}
+
+/// moves to the best non tabu neighbour at each iteration, even if it is worse than the current
+/// solution, ties are broken at random. if every move is tabu the one that expires first is made
+#[derive(Clone)]
+pub struct TabuSearch<P: Problem, N: NeighbourhoodIndirect<P>, T: TabuAttribute<P, N>, R: rng::Rng>
+{
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    rng: R,
+    ns: N,
+    attribute: T,
+    tenure: Tenure,
+    aspiration: Aspiration,
+}
+impl<P: Problem, N: NeighbourhoodIndirect<P>, T: TabuAttribute<P, N>, R: rng::Rng>
+    TabuSearch<P, N, T, R>
+{
+    pub fn new(
+        ns: N,
+        attribute: T,
+        initial_solution: (P::Sol, P::Obj),
+        tenure: Tenure,
+        aspiration: Aspiration,
+        rng: R,
+    ) -> Self {
+        if let Tenure::Random(min, max) = tenure {
+            debug_assert!(min <= max);
+        }
+        Self {
+            initial_solution: Some(initial_solution),
+            rng,
+            ns,
+            attribute,
+            tenure,
+            aspiration,
+        }
+    }
+    fn draw_tenure(&mut self) -> u64 {
+        match self.tenure {
+            Tenure::Fixed(t) => t,
+            Tenure::Random(min, max) => min + self.rng.next_u64() % (max - min + 1),
+        }
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirect<P>, T: TabuAttribute<P, N>, R: rng::Rng> Solver<P>
+    for TabuSearch<P, N, T, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let (mut solution, obj) = self.initial_solution.take().unwrap();
+        sk.add_solution(&solution, obj);
+        let max_tenure = match self.tenure {
+            Tenure::Fixed(t) => t,
+            Tenure::Random(_, max) => max,
+        };
+        // iteration until which each attribute is tabu
+        let mut tabu = HashMap::<T::Attr, u64>::new();
+        let mut it = 0u64;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            it += 1;
+            let mut best: Option<(N::NeighbourId, P::Obj, T::Attr)> = None;
+            let mut ties = 0;
+            // move whose attribute expires first, in case all of them are tabu
+            let mut oldest = None;
+            let mut oldest_expiry = u64::MAX;
+            for nid in self.ns.neighbourhood_id(&p, &solution) {
+                let nobj = self.ns.neighbour_obj(&p, &solution, &nid);
+                if best.as_ref().is_some_and(|b| nobj > b.1) {
+                    continue;
+                }
+                let attr = self.attribute.attribute(&p, &solution, &nid);
+                let expiry = tabu.get(&attr).copied().unwrap_or(0);
+                let admissible = expiry <= it
+                    || (self.aspiration == Aspiration::ImprovesBest && nobj < sk.best_obj());
+                if !admissible {
+                    if expiry < oldest_expiry {
+                        oldest = Some((nid, nobj, attr));
+                        oldest_expiry = expiry;
+                    }
+                    continue;
+                }
+                match &best {
+                    Some(b) if nobj == b.1 => {
+                        ties += 1;
+                        if self.rng.next_u64().is_multiple_of(ties) {
+                            best = Some((nid, nobj, attr));
+                        }
+                    }
+                    _ => {
+                        ties = 1;
+                        best = Some((nid, nobj, attr));
+                    }
+                }
+            }
+            let Some((nid, nobj, attr)) = best.or(oldest) else {
+                break;
+            };
+            solution = self.ns.neighbour(&p, solution, nid);
+            sk.add_solution(&solution, nobj);
+            let tenure = self.draw_tenure();
+            tabu.insert(attr, it + 1 + tenure);
+            // at most max_tenure + 1 attributes are tabu at any time
+            if tabu.len() as u64 > 2 * max_tenure + 16 {
+                tabu.retain(|_, e| *e > it);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::tests::*;
+
+    fn tabu_search(
+        p: &crate::sat::maxsat::MaxSat,
+        initial: Vec<bool>,
+        tenure: Tenure,
+        aspiration: Aspiration,
+        calls: u64,
+    ) -> Vec<usize> {
+        let obj = p.obj(&initial);
+        let attribute = |_: &_, _: &Vec<bool>, i: &usize| *i;
+        let search = TabuSearch::new(
+            Flip,
+            attribute,
+            (initial, obj),
+            tenure,
+            aspiration,
+            Splitmix64::from_u64(0),
+        );
+        flips(&trace(search, p, calls))
+    }
+
+    #[test]
+    fn tabu_for_tenure() {
+        // the cheapest variable is set first, then it is refused until it expires
+        let p = costs(&[1, 2, 4, 8]);
+        let moves = tabu_search(&p, vec![false; 4], Tenure::Fixed(2), Aspiration::Never, 4);
+        assert_eq!(moves, vec![0, 1, 2, 0]);
+        let p = costs(&[1, 2, 4]);
+        let moves = tabu_search(&p, vec![false; 3], Tenure::Fixed(1), Aspiration::Never, 3);
+        assert_eq!(moves, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn every_move_tabu() {
+        // x0 then x1 are set, then the move whose attribute expires first is made each time
+        let p = costs(&[1, 2]);
+        let moves = tabu_search(&p, vec![false; 2], Tenure::Fixed(5), Aspiration::Never, 6);
+        assert_eq!(moves, vec![0, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn aspiration() {
+        // every move shares the same attribute, so everything is tabu after the first move
+        let p = costs(&[2, 1]);
+        let initial = vec![true, true];
+        let obj = p.obj(&initial);
+        let run = |aspiration| {
+            let search = TabuSearch::new(
+                Flip,
+                |_: &_, _: &Vec<bool>, _: &usize| (),
+                (initial.clone(), obj),
+                Tenure::Fixed(5),
+                aspiration,
+                Splitmix64::from_u64(0),
+            );
+            flips(&trace(search, &p, 2))
+        };
+        // x0 is unset first, then unsetting x1 gives a new best solution
+        assert_eq!(run(Aspiration::ImprovesBest), vec![0, 1]);
+        // without aspiration the first tabu move is made instead
+        assert_eq!(run(Aspiration::Never), vec![0, 0]);
+    }
+}
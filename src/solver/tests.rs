@@ -0,0 +1,288 @@
+//! small weighted MaxSat instances with the neighbourhoods and trees the solvers need
+
+use std::cell::Cell;
+
+use crate::core::neighbour_space::*;
+use crate::core::tree_space::*;
+use crate::core::*;
+use crate::sat::cnf::*;
+use crate::sat::maxsat::MaxSat;
+
+/// random soft clauses of 1 to 3 literals over n variables, every assignment is feasible
+pub fn problem(seed: u64, n: usize) -> MaxSat {
+    let mut rng = Splitmix64::from_u64(seed);
+    let mut p = MaxSat::new();
+    p.num_vars = n;
+    for _ in 0..3 * n {
+        let len = 1 + rng.next_u64() as usize % 3;
+        let c: Vec<Lit> = (0..len)
+            .map(|_| {
+                Lit::new(
+                    rng.next_u64() as usize % n,
+                    rng.next_u64().is_multiple_of(2),
+                )
+            })
+            .collect();
+        p.add_soft(&c, 1 + rng.next_u64() % 5);
+    }
+    p
+}
+
+pub fn optimum(p: &MaxSat) -> SatObj {
+    let n = p.num_vars;
+    (0..1u32 << n)
+        .map(|code| p.obj(&(0..n).map(|i| code >> i & 1 == 1).collect()))
+        .min()
+        .unwrap()
+}
+
+pub fn all_false(p: &MaxSat) -> (Vec<bool>, SatObj) {
+    let sol = vec![false; p.num_vars];
+    let obj = p.obj(&sol);
+    (sol, obj)
+}
+
+/// setting variable i to true costs weights[i]
+pub fn costs(weights: &[u64]) -> MaxSat {
+    let mut p = MaxSat::new();
+    p.num_vars = weights.len();
+    for (i, &w) in weights.iter().enumerate() {
+        p.add_soft(&[Lit::neg(i)], w);
+    }
+    p
+}
+
+/// keeps every solution it is given, in order
+pub struct Trace(pub Vec<(Vec<bool>, SatObj)>);
+impl SolutionKeeper<MaxSat> for Trace {
+    fn add_solution(&mut self, sol: &Vec<bool>, obj: SatObj) {
+        self.0.push((sol.clone(), obj));
+    }
+    fn add_solution_fn<F: FnOnce() -> Vec<bool>>(&mut self, f: F, obj: SatObj) {
+        self.0.push((f(), obj));
+    }
+    fn add_dual_bound(&mut self, _db: SatObj) {}
+    fn iter(&mut self) {}
+    fn best_solution(&self) -> Option<(Vec<bool>, SatObj)> {
+        self.0.iter().min_by_key(|s| s.1).cloned()
+    }
+}
+
+/// solutions given to the keeper, in order
+pub fn trace<S: Solver<MaxSat>>(mut solver: S, p: &MaxSat, calls: u64) -> Vec<(Vec<bool>, SatObj)> {
+    let mut sk = Trace(vec![]);
+    solver.solve(p.clone(), &mut sk, CallStop(calls));
+    for (sol, obj) in sk.0.iter() {
+        assert_eq!(p.obj(sol), *obj);
+    }
+    sk.0
+}
+
+/// variable flipped between consecutive solutions, which must differ by one variable
+pub fn flips(solutions: &[(Vec<bool>, SatObj)]) -> Vec<usize> {
+    solutions
+        .windows(2)
+        .map(|w| {
+            let diff: Vec<usize> = (0..w[0].0.len())
+                .filter(|&i| w[0].0[i] != w[1].0[i])
+                .collect();
+            assert_eq!(diff.len(), 1);
+            diff[0]
+        })
+        .collect()
+}
+
+/// stops after the given number of calls
+#[derive(Clone)]
+pub struct CallStop(pub u64);
+impl<O: Objective> StopCondition<O> for CallStop {
+    fn stop(&mut self, _primal_bound: O, _dual_bound: O) -> bool {
+        if self.0 == 0 {
+            return true;
+        }
+        self.0 -= 1;
+        false
+    }
+}
+
+pub fn run<S: Solver<MaxSat>>(
+    mut solver: S,
+    p: &MaxSat,
+    calls: u64,
+) -> SimpleSolutionKeeper<MaxSat> {
+    let mut sk = SimpleSolutionKeeper::default();
+    solver.solve(p.clone(), &mut sk, CallStop(calls));
+    if let Some((sol, obj)) = sk.best_solution() {
+        assert_eq!(p.obj(&sol), obj);
+    }
+    sk
+}
+
+/// flips one variable
+#[derive(Clone)]
+pub struct Flip;
+fn flipped(sol: &[bool], i: usize) -> Vec<bool> {
+    let mut sol = sol.to_vec();
+    sol[i] = !sol[i];
+    sol
+}
+impl NeighbourhoodIndirect<MaxSat> for Flip {
+    type NeighbourId = usize;
+    fn neighbourhood_id(&self, p: &MaxSat, _node: &Vec<bool>) -> impl Iterator<Item = usize> {
+        0..p.num_vars
+    }
+    fn neighbour_obj(&self, p: &MaxSat, node: &Vec<bool>, nid: &usize) -> SatObj {
+        p.obj(&flipped(node, *nid))
+    }
+    fn neighbour(&self, _p: &MaxSat, node: Vec<bool>, nid: usize) -> Vec<bool> {
+        flipped(&node, nid)
+    }
+}
+impl NeighbourhoodIndirectRandom<MaxSat> for Flip {
+    type NeighbourId = usize;
+    fn random_neighbour_id<R: Rng>(&self, p: &MaxSat, _node: &Vec<bool>, rng: &mut R) -> usize {
+        rng.next_u64() as usize % p.num_vars
+    }
+    fn random_neighbour(&self, _p: &MaxSat, node: Vec<bool>, nid: usize) -> Vec<bool> {
+        flipped(&node, nid)
+    }
+    fn neighbour_obj(&self, p: &MaxSat, node: &Vec<bool>, nid: &usize) -> SatObj {
+        p.obj(&flipped(node, *nid))
+    }
+}
+impl NeighbourhoodDirectRandom<MaxSat> for Flip {
+    fn random_neighbour<R: Rng>(&self, p: &MaxSat, node: &Vec<bool>, rng: &mut R) -> Vec<bool> {
+        flipped(node, rng.next_u64() as usize % p.num_vars)
+    }
+}
+
+/// flips the variables in the given order instead of random ones, then the last one
+#[derive(Clone)]
+pub struct Script(Vec<usize>, Cell<usize>);
+impl Script {
+    pub fn new(moves: &[usize]) -> Self {
+        Self(moves.to_vec(), Cell::new(0))
+    }
+    fn next(&self) -> usize {
+        let i = self.1.get();
+        self.1.set(i + 1);
+        self.0[i.min(self.0.len() - 1)]
+    }
+}
+impl NeighbourhoodIndirectRandom<MaxSat> for Script {
+    type NeighbourId = usize;
+    fn random_neighbour_id<R: Rng>(&self, _p: &MaxSat, _node: &Vec<bool>, _rng: &mut R) -> usize {
+        self.next()
+    }
+    fn random_neighbour(&self, _p: &MaxSat, node: Vec<bool>, nid: usize) -> Vec<bool> {
+        flipped(&node, nid)
+    }
+    fn neighbour_obj(&self, p: &MaxSat, node: &Vec<bool>, nid: &usize) -> SatObj {
+        p.obj(&flipped(node, *nid))
+    }
+}
+impl NeighbourhoodDirectRandom<MaxSat> for Script {
+    fn random_neighbour<R: Rng>(&self, _p: &MaxSat, node: &Vec<bool>, _rng: &mut R) -> Vec<bool> {
+        flipped(node, self.next())
+    }
+}
+
+/// assigns the variables in order, a node is the prefix of the values
+#[derive(Clone)]
+pub struct Assign(pub MaxSat);
+impl Assign {
+    /// weight of the soft clauses whose variables are all assigned and that are violated
+    fn violated(&self, n: &[bool]) -> SatObj {
+        let w: u64 = self
+            .0
+            .soft
+            .iter()
+            .filter(|(c, _)| c.iter().all(|l| l.var() < n.len() && !l.eval(n)))
+            .map(|(_, w)| w)
+            .sum();
+        SatObj(w as i64)
+    }
+    fn with(n: &[bool], value: bool) -> Vec<bool> {
+        let mut n = n.to_vec();
+        n.push(value);
+        n
+    }
+}
+impl Tree<MaxSat> for Assign {
+    type Node = Vec<bool>;
+    fn root(&self) -> Vec<bool> {
+        vec![]
+    }
+    fn objective(&self, n: &Vec<bool>) -> Option<SatObj> {
+        (n.len() == self.0.num_vars).then(|| self.0.obj(n))
+    }
+    fn to_solution(&self, n: &Vec<bool>) -> Option<Vec<bool>> {
+        (n.len() == self.0.num_vars).then(|| n.clone())
+    }
+    fn from(p: &MaxSat) -> Self {
+        Self(p.clone())
+    }
+}
+impl TreeIndirect<MaxSat> for Assign {
+    type ChildId = bool;
+    fn children_id(&self, n: &Vec<bool>) -> impl Iterator<Item = bool> {
+        let k = if n.len() < self.0.num_vars { 2 } else { 0 };
+        [false, true].into_iter().take(k)
+    }
+    fn child(&self, n: &Vec<bool>, cid: &bool) -> Vec<bool> {
+        Self::with(n, *cid)
+    }
+}
+impl TreeGuided<MaxSat> for Assign {
+    type Guide = SatObj;
+    fn goodness(&self, n: &Vec<bool>) -> SatObj {
+        self.violated(n)
+    }
+}
+impl TreeIndirectGuided<MaxSat> for Assign {
+    fn child_goodness(&self, n: &Vec<bool>, cid: &bool) -> SatObj {
+        self.violated(&Self::with(n, *cid))
+    }
+}
+impl TreeBounded<MaxSat> for Assign {
+    fn primal_bound(&self, n: &Vec<bool>) -> SatObj {
+        let mut sol = n.clone();
+        sol.resize(self.0.num_vars, false);
+        self.0.obj(&sol)
+    }
+    fn dual_bound(&self, n: &Vec<bool>, _primal: SatObj) -> SatObj {
+        self.violated(n)
+    }
+}
+impl TreeIndirectBounded<MaxSat> for Assign {
+    fn child_primal_bound(&self, n: &Vec<bool>, cid: &bool) -> SatObj {
+        self.primal_bound(&Self::with(n, *cid))
+    }
+    fn child_dual_bound(&self, n: &Vec<bool>, cid: &bool, primal: SatObj) -> SatObj {
+        self.dual_bound(&Self::with(n, *cid), primal)
+    }
+}
+impl TreeDirectRandom<MaxSat> for Assign {
+    fn random_child<R: Rng>(&self, n: &Vec<bool>, rng: &mut R) -> Option<Vec<bool>> {
+        self.random_child_consuming(n.clone(), rng)
+    }
+    fn random_child_consuming<R: Rng>(&self, mut n: Vec<bool>, rng: &mut R) -> Option<Vec<bool>> {
+        if n.len() == self.0.num_vars {
+            return None;
+        }
+        n.push(rng.next_u64().is_multiple_of(2));
+        Some(n)
+    }
+}
+impl TreeRollback<MaxSat> for Assign {
+    type RollbackInfo = ();
+    fn rollback(&self, mut n: Vec<bool>, _info: ()) -> Vec<bool> {
+        n.pop();
+        n
+    }
+}
+impl TreeRollbackDirectRandom<MaxSat> for Assign {
+    fn random_child_rollback<R: Rng>(&self, n: Vec<bool>, rng: &mut R) -> Option<(Vec<bool>, ())> {
+        self.random_child_consuming(n, rng).map(|n| (n, ()))
+    }
+}
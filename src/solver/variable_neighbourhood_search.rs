@@ -0,0 +1,311 @@
+use crate::core::neighbour_space::*;
+use crate::core::*;
+use crate::solver::local_search::LocalSearch;
+
+/// ordered neighbourhoods that can be explored exhaustively, implemented for tuples
+/// of up to 6 NeighbourhoodIndirect with different move types and for vectors of one type
+pub trait NeighbourhoodList<P: Problem>: Clone {
+    fn num_neighbourhoods(&self) -> usize;
+    /// moves to the best neighbour of the k-th neighbourhood if it improves the solution,
+    /// returns whether it did
+    fn best_improvement(
+        &self,
+        k: usize,
+        p: &P,
+        solution: (P::Sol, P::Obj),
+    ) -> ((P::Sol, P::Obj), bool);
+}
+
+/// ordered neighbourhoods that can be sampled, implemented for tuples of up to 6
+/// NeighbourhoodIndirectRandom with different move types and for vectors of one type
+pub trait RandomNeighbourhoodList<P: Problem>: Clone {
+    fn num_neighbourhoods(&self) -> usize;
+    /// random neighbour of the k-th neighbourhood
+    fn random_neighbour<R: rng::Rng>(
+        &self,
+        k: usize,
+        p: &P,
+        solution: P::Sol,
+        rng: &mut R,
+    ) -> (P::Sol, P::Obj);
+}
+
+fn best_improvement<P: Problem, N: NeighbourhoodIndirect<P>>(
+    ns: &N,
+    p: &P,
+    (solution, obj): (P::Sol, P::Obj),
+) -> ((P::Sol, P::Obj), bool) {
+    let mut best_nid = None;
+    let mut best_nobj = obj;
+    for nid in ns.neighbourhood_id(p, &solution) {
+        let nobj = ns.neighbour_obj(p, &solution, &nid);
+        if nobj < best_nobj {
+            best_nobj = nobj;
+            best_nid = Some(nid);
+        }
+    }
+    match best_nid {
+        Some(nid) => ((ns.neighbour(p, solution, nid), best_nobj), true),
+        None => ((solution, obj), false),
+    }
+}
+
+fn random_neighbour<P: Problem, N: NeighbourhoodIndirectRandom<P>, R: rng::Rng>(
+    ns: &N,
+    p: &P,
+    solution: P::Sol,
+    rng: &mut R,
+) -> (P::Sol, P::Obj) {
+    let nid = ns.random_neighbour_id(p, &solution, rng);
+    let obj = ns.neighbour_obj(p, &solution, &nid);
+    (ns.random_neighbour(p, solution, nid), obj)
+}
+
+impl<P: Problem, N: NeighbourhoodIndirect<P>> NeighbourhoodList<P> for Vec<N> {
+    fn num_neighbourhoods(&self) -> usize {
+        Vec::len(self)
+    }
+    fn best_improvement(
+        &self,
+        k: usize,
+        p: &P,
+        solution: (P::Sol, P::Obj),
+    ) -> ((P::Sol, P::Obj), bool) {
+        best_improvement(&self[k], p, solution)
+    }
+}
+impl<P: Problem, N: NeighbourhoodIndirectRandom<P>> RandomNeighbourhoodList<P> for Vec<N> {
+    fn num_neighbourhoods(&self) -> usize {
+        Vec::len(self)
+    }
+    fn random_neighbour<R: rng::Rng>(
+        &self,
+        k: usize,
+        p: &P,
+        solution: P::Sol,
+        rng: &mut R,
+    ) -> (P::Sol, P::Obj) {
+        random_neighbour(&self[k], p, solution, rng)
+    }
+}
+
+macro_rules! impl_neighbourhood_lists {
+    ($($n:ident $i:tt),+) => {
+        impl<P: Problem, $($n: NeighbourhoodIndirect<P>),+> NeighbourhoodList<P> for ($($n,)+) {
+            fn num_neighbourhoods(&self) -> usize {
+                [$($i),+].len()
+            }
+            fn best_improvement(
+                &self,
+                k: usize,
+                p: &P,
+                solution: (P::Sol, P::Obj),
+            ) -> ((P::Sol, P::Obj), bool) {
+                match k {
+                    $($i => best_improvement(&self.$i, p, solution),)+
+                    _ => panic!("no neighbourhood {}", k),
+                }
+            }
+        }
+        impl<P: Problem, $($n: NeighbourhoodIndirectRandom<P>),+> RandomNeighbourhoodList<P>
+            for ($($n,)+)
+        {
+            fn num_neighbourhoods(&self) -> usize {
+                [$($i),+].len()
+            }
+            fn random_neighbour<R: rng::Rng>(
+                &self,
+                k: usize,
+                p: &P,
+                solution: P::Sol,
+                rng: &mut R,
+            ) -> (P::Sol, P::Obj) {
+                match k {
+                    $($i => random_neighbour(&self.$i, p, solution, rng),)+
+                    _ => panic!("no neighbourhood {}", k),
+                }
+            }
+        }
+    };
+}
+impl_neighbourhood_lists!(N0 0);
+impl_neighbourhood_lists!(N0 0, N1 1);
+impl_neighbourhood_lists!(N0 0, N1 1, N2 2);
+impl_neighbourhood_lists!(N0 0, N1 1, N2 2, N3 3);
+impl_neighbourhood_lists!(N0 0, N1 1, N2 2, N3 3, N4 4);
+impl_neighbourhood_lists!(N0 0, N1 1, N2 2, N3 3, N4 4, N5 5);
+
+/// variable neighbourhood descent: best improvement in the first neighbourhood that has one,
+/// going back to the first neighbourhood after each improvement, stops at a solution that is
+/// a local optimum for all of them
+#[derive(Clone)]
+pub struct VariableNeighbourhoodDescent<P: Problem, L: NeighbourhoodList<P>> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    nss: L,
+}
+impl<P: Problem, L: NeighbourhoodList<P>> VariableNeighbourhoodDescent<P, L> {
+    /// panics if nss is empty
+    pub fn new(nss: L, initial_solution: (P::Sol, P::Obj)) -> Self {
+        assert!(nss.num_neighbourhoods() > 0, "no neighbourhood");
+        Self {
+            initial_solution: Some(initial_solution),
+            nss,
+        }
+    }
+}
+impl<P: Problem, L: NeighbourhoodList<P>> Solver<P> for VariableNeighbourhoodDescent<P, L> {
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let initial_solution = self.initial_solution.take().unwrap();
+        sk.add_solution(&initial_solution.0, initial_solution.1);
+        self.improve(&p, initial_solution, sk, &mut stop);
+    }
+}
+impl<P: Problem, L: NeighbourhoodList<P>> LocalSearch<P> for VariableNeighbourhoodDescent<P, L> {
+    fn improve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: &P,
+        mut solution: (P::Sol, P::Obj),
+        sk: &mut SK,
+        stop: &mut S,
+    ) -> (P::Sol, P::Obj) {
+        let mut k = 0;
+        while k < self.nss.num_neighbourhoods() {
+            if stop.stop(solution.1, P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let improved;
+            (solution, improved) = self.nss.best_improvement(k, p, solution);
+            if improved {
+                sk.add_solution(&solution.0, solution.1);
+                k = 0;
+            } else {
+                k += 1;
+            }
+        }
+        solution
+    }
+}
+
+/// basic variable neighbourhood search: shakes the current solution with a random move of the
+/// k-th neighbourhood and runs the local search from it, the result replaces the current
+/// solution if it is better and k goes back to the first neighbourhood, otherwise k moves to
+/// the next one (cyclically)
+#[derive(Clone)]
+pub struct VariableNeighbourhoodSearch<
+    P: Problem,
+    L: RandomNeighbourhoodList<P>,
+    LS: LocalSearch<P>,
+    R: rng::Rng,
+> {
+    initial_solution: Option<(P::Sol, P::Obj)>,
+    nss: L,
+    local_search: LS,
+    rng: R,
+}
+impl<P: Problem, L: RandomNeighbourhoodList<P>, LS: LocalSearch<P>, R: rng::Rng>
+    VariableNeighbourhoodSearch<P, L, LS, R>
+{
+    /// panics if nss is empty
+    pub fn new(nss: L, local_search: LS, initial_solution: (P::Sol, P::Obj), rng: R) -> Self {
+        assert!(nss.num_neighbourhoods() > 0, "no neighbourhood");
+        Self {
+            initial_solution: Some(initial_solution),
+            nss,
+            local_search,
+            rng,
+        }
+    }
+}
+impl<P: Problem, L: RandomNeighbourhoodList<P>, LS: LocalSearch<P>, R: rng::Rng> Solver<P>
+    for VariableNeighbourhoodSearch<P, L, LS, R>
+{
+    fn solve<SK: SolutionKeeper<P>, S: stop_condition::StopCondition<P::Obj>>(
+        &mut self,
+        p: P,
+        sk: &mut SK,
+        mut stop: S,
+    ) {
+        let initial_solution = self.initial_solution.take().unwrap();
+        sk.add_solution(&initial_solution.0, initial_solution.1);
+        let mut current = self
+            .local_search
+            .improve(&p, initial_solution, sk, &mut stop);
+        let mut k = 0;
+        loop {
+            if stop.stop(sk.best_obj(), P::Obj::unbounded()) {
+                break;
+            }
+            sk.iter();
+            let (solution, obj) =
+                self.nss
+                    .random_neighbour(k, &p, current.0.clone(), &mut self.rng);
+            sk.add_solution(&solution, obj);
+            let candidate = self
+                .local_search
+                .improve(&p, (solution, obj), sk, &mut stop);
+            if candidate.1 < current.1 {
+                current = candidate;
+                k = 0;
+            } else {
+                k = (k + 1) % self.nss.num_neighbourhoods();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::cnf::*;
+    use crate::sat::maxsat::MaxSat;
+    use crate::solver::tests::*;
+
+    /// flips one of the given variables
+    #[derive(Clone)]
+    struct Vars(Vec<usize>);
+    impl NeighbourhoodIndirect<MaxSat> for Vars {
+        type NeighbourId = usize;
+        fn neighbourhood_id(&self, _p: &MaxSat, _node: &Vec<bool>) -> impl Iterator<Item = usize> {
+            self.0.clone().into_iter()
+        }
+        fn neighbour_obj(&self, p: &MaxSat, node: &Vec<bool>, nid: &usize) -> SatObj {
+            p.obj(&self.neighbour(p, node.clone(), *nid))
+        }
+        fn neighbour(&self, _p: &MaxSat, mut node: Vec<bool>, nid: usize) -> Vec<bool> {
+            node[nid] = !node[nid];
+            node
+        }
+    }
+
+    #[test]
+    fn descent_back_to_first_neighbourhood() {
+        // setting x0 only pays off once x1 is set, which only the second neighbourhood does
+        let mut p = MaxSat::new();
+        p.num_vars = 2;
+        p.add_soft(&[Lit::pos(1)], 2);
+        p.add_soft(&[Lit::neg(1), Lit::pos(0)], 1);
+        let solver =
+            VariableNeighbourhoodDescent::new((Vars(vec![0]), Vars(vec![1])), all_false(&p));
+        let solutions = trace(solver, &p, 100);
+        assert_eq!(flips(&solutions), [1, 0]);
+        assert_eq!(solutions.last().unwrap().1, SatObj(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "no neighbourhood")]
+    fn empty_neighbourhood_list() {
+        let p = problem(0, 4);
+        VariableNeighbourhoodSearch::new(
+            Vec::<Flip>::new(),
+            VariableNeighbourhoodDescent::new(vec![Flip], all_false(&p)),
+            all_false(&p),
+            Splitmix64::from_u64(0),
+        );
+    }
+}